@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::{Local, TimeZone};
+use parking_lot::Mutex;
+use crate::store;
+use crate::types::{AppState, StateTransition, StatsBucket, StatsReport, TaskState};
+use crate::utils::current_timestamp;
+
+/// Default stats window when the caller gives no `since`
+const DEFAULT_STATS_WINDOW_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// A span of time a task spent in one state, clipped to the query window
+#[derive(Debug, Clone)]
+pub struct StateInterval {
+    pub task_id: String,
+    pub project_id: String,
+    pub agent: String,
+    pub state: TaskState,
+    pub start: i64,
+    pub end: i64,
+    /// When the task entered the state (may be before the window)
+    pub entered_at: i64,
+    /// When and to which state the task moved on, if it has
+    pub left: Option<(i64, TaskState)>,
+}
+
+impl StateInterval {
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+/// Rebuild state intervals overlapping `[since, until)` from transitions ordered by task and time.
+/// Open intervals only run to `until` for tasks still in that state on the dashboard.
+pub fn build_intervals(
+    transitions: &[StateTransition],
+    live_states: &HashMap<String, TaskState>,
+    since: i64,
+    until: i64,
+) -> Vec<StateInterval> {
+    let mut intervals = Vec::new();
+
+    for (index, transition) in transitions.iter().enumerate() {
+        let next = transitions
+            .get(index + 1)
+            .filter(|next| next.task_id == transition.task_id);
+
+        let (raw_end, left) = match next {
+            Some(next) => (next.timestamp, Some((next.timestamp, next.to))),
+            None if !transition.to.is_terminal()
+                && live_states.get(&transition.task_id) == Some(&transition.to) => (until, None),
+            None => (transition.timestamp, None),
+        };
+
+        let start = transition.timestamp.max(since);
+        let end = raw_end.min(until);
+        let entered_in_window = transition.timestamp >= since && transition.timestamp < until;
+        if end <= start && !entered_in_window {
+            continue;
+        }
+
+        intervals.push(StateInterval {
+            task_id: transition.task_id.clone(),
+            project_id: transition.project_id.clone(),
+            agent: transition.agent.clone(),
+            state: transition.to,
+            start,
+            end: end.max(start),
+            entered_at: transition.timestamp,
+            left,
+        });
+    }
+
+    intervals
+}
+
+#[derive(Default)]
+struct BucketAccumulator {
+    label: String,
+    working_seconds: i64,
+    pending_seconds: i64,
+    sessions: HashSet<String>,
+    error_sessions: HashSet<String>,
+    response_latencies: Vec<i64>,
+}
+
+impl BucketAccumulator {
+    fn add_time(&mut self, interval: &StateInterval, seconds: i64) {
+        match interval.state {
+            TaskState::Working => self.working_seconds += seconds,
+            TaskState::Pending => self.pending_seconds += seconds,
+            _ => {}
+        }
+        self.sessions.insert(interval.task_id.clone());
+        if interval.state == TaskState::Error {
+            self.error_sessions.insert(interval.task_id.clone());
+        }
+    }
+
+    fn into_bucket(self, key: String) -> StatsBucket {
+        let sessions = self.sessions.len();
+        let error_sessions = self.error_sessions.len();
+        StatsBucket {
+            key,
+            label: self.label,
+            working_seconds: self.working_seconds,
+            pending_seconds: self.pending_seconds,
+            sessions,
+            error_sessions,
+            error_rate: if sessions == 0 { 0.0 } else { error_sessions as f64 / sessions as f64 },
+            median_response_seconds: median(self.response_latencies),
+        }
+    }
+}
+
+/// Median of the values, averaging the middle pair for even counts
+pub fn median(mut values: Vec<i64>) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2 } else { values[mid] })
+}
+
+/// Local calendar day of a timestamp and the timestamp of the following midnight
+fn local_day(timestamp: i64) -> (String, i64) {
+    let Some(datetime) = Local.timestamp_opt(timestamp, 0).single() else {
+        return ("unknown".to_string(), timestamp + 24 * 60 * 60);
+    };
+    let date = datetime.date_naive();
+    let next_midnight = date
+        .succ_opt()
+        .and_then(|next| next.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|midnight| midnight.timestamp())
+        .unwrap_or(timestamp + 24 * 60 * 60);
+    (date.format("%Y-%m-%d").to_string(), next_midnight)
+}
+
+/// Split an interval into per-day durations (zero-length intervals count on the day they started)
+fn split_by_day(interval: &StateInterval) -> Vec<(String, i64)> {
+    let mut parts = Vec::new();
+    let mut cursor = interval.start;
+    loop {
+        let (day, next_midnight) = local_day(cursor);
+        let part_end = next_midnight.min(interval.end);
+        parts.push((day, (part_end - cursor).max(0)));
+        if part_end >= interval.end {
+            break;
+        }
+        cursor = part_end;
+    }
+    parts
+}
+
+/// Aggregate intervals into per-project, per-agent and per-day buckets
+pub fn compute_stats(
+    intervals: &[StateInterval],
+    project_names: &HashMap<String, String>,
+    since: i64,
+    until: i64,
+) -> StatsReport {
+    let mut by_project: BTreeMap<String, BucketAccumulator> = BTreeMap::new();
+    let mut by_agent: BTreeMap<String, BucketAccumulator> = BTreeMap::new();
+    let mut by_day: BTreeMap<String, BucketAccumulator> = BTreeMap::new();
+
+    for interval in intervals {
+        let project = by_project.entry(interval.project_id.clone()).or_insert_with(|| BucketAccumulator {
+            label: project_names.get(&interval.project_id).cloned().unwrap_or_else(|| interval.project_id.clone()),
+            ..Default::default()
+        });
+        project.add_time(interval, interval.duration());
+
+        let agent = by_agent.entry(interval.agent.clone()).or_insert_with(|| BucketAccumulator {
+            label: interval.agent.clone(),
+            ..Default::default()
+        });
+        agent.add_time(interval, interval.duration());
+
+        for (day, seconds) in split_by_day(interval) {
+            by_day.entry(day.clone())
+                .or_insert_with(|| BucketAccumulator { label: day, ..Default::default() })
+                .add_time(interval, seconds);
+        }
+
+        // Response latency: how long a PENDING task waited before work resumed
+        if let (TaskState::Pending, Some((left_at, TaskState::Working))) = (interval.state, interval.left) {
+            if left_at >= since && left_at < until {
+                let latency = left_at - interval.entered_at;
+                project.response_latencies.push(latency);
+                agent.response_latencies.push(latency);
+                let (day, _) = local_day(interval.entered_at.max(since));
+                if let Some(bucket) = by_day.get_mut(&day) {
+                    bucket.response_latencies.push(latency);
+                }
+            }
+        }
+    }
+
+    let into_buckets = |map: BTreeMap<String, BucketAccumulator>| {
+        map.into_iter().map(|(key, acc)| acc.into_bucket(key)).collect()
+    };
+
+    StatsReport {
+        since,
+        until,
+        by_project: into_buckets(by_project),
+        by_agent: into_buckets(by_agent),
+        by_day: into_buckets(by_day),
+    }
+}
+
+/// Intervals overlapping the window, built from the transition history and live task states
+pub fn load_intervals(
+    state: &Mutex<AppState>,
+    since: i64,
+    until: i64,
+) -> Result<(Vec<StateInterval>, HashMap<String, String>), String> {
+    // Query the DB first; app state is only locked after the DB lock is released
+    let transitions = store::with_connection(|conn| store::load_transitions_in_window(conn, since, until))?;
+
+    let state = state.lock();
+    let live_states = state.tasks.iter().map(|(id, task)| (id.clone(), task.state)).collect();
+    let project_names = state.projects.iter().map(|(id, project)| (id.clone(), project.name.clone())).collect();
+    drop(state);
+
+    Ok((build_intervals(&transitions, &live_states, since, until), project_names))
+}
+
+/// Time-in-state statistics for `[since, until)`, defaulting to the last seven days
+pub fn collect_stats(state: &Mutex<AppState>, since: Option<i64>, until: Option<i64>) -> Result<StatsReport, String> {
+    let until = until.unwrap_or_else(current_timestamp);
+    let since = since.unwrap_or(until - DEFAULT_STATS_WINDOW_SECONDS);
+    if since >= until {
+        return Err("`since` must be before `until`".to_string());
+    }
+
+    let (intervals, project_names) = load_intervals(state, since, until)?;
+    Ok(compute_stats(&intervals, &project_names, since, until))
+}
@@ -0,0 +1,141 @@
+use std::{path::Path, sync::Arc};
+use axum::{middleware, routing::{get, post}, Router};
+use log::info;
+use crate::handlers::*;
+use crate::state::SharedState;
+use crate::types::AppSettings;
+
+pub use crate::events::{EventBus, EventSink, StreamEvent};
+pub use crate::retention::SweepOutcome;
+
+/// Everything the HTTP handlers need: the app state they mutate and where their events go.
+/// The router depends only on this, so it can be driven in tests without a webview.
+#[derive(Clone)]
+pub struct ApiContext {
+    pub state: SharedState,
+    pub events: Arc<dyn EventSink>,
+}
+
+impl ApiContext {
+    pub fn new(state: SharedState, events: Arc<dyn EventSink>) -> Self {
+        Self { state, events }
+    }
+}
+
+/// Open (or create) the SQLite store the API persists to
+pub fn open_store(path: &Path) -> Result<(), String> {
+    crate::store::open_store(path)
+}
+
+/// Load the settings file and start the background jobs as headless mode does, returning the settings applied
+pub fn start_headless(ctx: &ApiContext) -> AppSettings {
+    crate::headless::start(ctx)
+}
+
+/// Load the notification rules the handlers evaluate
+pub fn configure_notifications(settings: &AppSettings) -> Result<(), String> {
+    crate::notifications::configure(settings)
+}
+
+/// Load the webhooks that transitions are delivered to
+pub fn configure_webhooks(settings: &AppSettings) -> Result<(), String> {
+    crate::webhooks::configure(settings)
+}
+
+/// Load the patterns masked in task details and debug buffers
+pub fn configure_redaction(settings: &AppSettings) -> Result<(), String> {
+    crate::redaction::configure(settings)
+}
+
+/// Load the schedule for writing digest reports to the data directory
+pub fn configure_reports(settings: &AppSettings) -> Result<(), String> {
+    crate::reports::configure(settings)
+}
+
+/// Load the thresholds for re-notifying about tasks left waiting
+pub fn configure_escalation(settings: &AppSettings) {
+    crate::escalation::configure(settings)
+}
+
+/// Run one escalation check now, as the background scheduler does periodically
+pub fn check_escalations(ctx: &ApiContext) {
+    crate::escalation::check(ctx, crate::utils::current_timestamp())
+}
+
+/// Load the timeout after which tasks without heartbeats are marked STALE
+pub fn configure_heartbeats(settings: &AppSettings) {
+    crate::heartbeat::configure(settings)
+}
+
+/// Mark tasks whose wrapper has gone quiet as STALE now, as the background reaper does periodically.
+/// Returns how many were marked.
+pub fn check_heartbeats(ctx: &ApiContext) -> usize {
+    crate::heartbeat::check(ctx, crate::utils::current_timestamp())
+}
+
+/// Load the rules for cleaning up finished tasks and debug data
+pub fn configure_retention(settings: &AppSettings) {
+    crate::retention::configure(settings)
+}
+
+/// Apply the retention rules now, as the background sweeper does periodically
+pub fn run_retention(ctx: &ApiContext) -> SweepOutcome {
+    crate::retention::sweep(ctx, crate::utils::current_timestamp())
+}
+
+/// End the rate limiter's blocks now, as happens a minute after they start
+pub fn expire_rate_limits() {
+    crate::audit::expire_blocks()
+}
+
+/// Build the HTTP API router
+pub fn build_router(ctx: ApiContext) -> Router {
+    // No CORS configuration necessary: only non-browser clients (Node CLI) call this server.
+    // let cors = CorsLayer::new()
+    //     .allow_origin(tower_http::cors::Any)
+    //     .allow_methods([axum::http::Method::GET, axum::http::Method::POST])
+    //     .allow_headers([
+    //         axum::http::header::CONTENT_TYPE,
+    //         axum::http::header::AUTHORIZATION,
+    //     ]);
+
+    let router = Router::new()
+        .route("/v1/state", get(get_state))
+        .route("/v1/events", get(stream_events))
+        .route("/v1/tasks/upsert", post(upsert_task))
+        .route("/v1/tasks/state", post(update_task_state))
+        .route("/v1/tasks/details", post(update_task_details))
+        .route("/v1/tasks/done", post(mark_task_done))
+        .route("/v1/tasks/delete", post(delete_task))
+        .route("/v1/tasks/archive", post(archive_task))
+        .route("/v1/tasks/restore", post(restore_task))
+        .route("/v1/tasks/pin", post(pin_task))
+        .route("/v1/tasks/snooze", post(snooze_task_notifications))
+        .route("/v1/tasks/heartbeat", post(heartbeat_task))
+        .route("/v1/tasks/{task_id}/history", get(get_task_history))
+        .route("/v1/archive", get(search_archive))
+        .route("/v1/stats", get(get_stats))
+        .route("/v1/reports/{period}", get(get_report))
+        .route("/v1/webhooks/deliveries", get(get_webhook_deliveries))
+        .route("/v1/tokens", get(list_tokens).post(create_token))
+        .route("/v1/tokens/rotate", post(rotate_token))
+        .route("/v1/tokens/revoke", post(revoke_token))
+        .route("/v1/audit", get(get_auth_audit))
+        .route("/v1/setup/status", get(get_setup_status))
+        .route("/v1/health", get(health_check))
+        .route("/v1/debug/patterns", get(get_debug_patterns))
+        .route("/v1/debug/patterns/{task_id}", get(get_debug_patterns_for_task))
+        .route("/v1/debug/update", post(update_debug_data));
+
+    // Prometheus scrape endpoint is opt-in
+    let router = if crate::metrics::metrics_enabled() {
+        info!("Metrics endpoint enabled at /metrics");
+        router.route("/metrics", get(get_metrics))
+    } else {
+        router
+    };
+
+    router
+        .layer(middleware::from_fn(crate::audit::track))
+        .with_state(ctx)
+}
@@ -0,0 +1,89 @@
+use log::{error, info};
+use crate::api::ApiContext;
+use crate::events::emit_task_event;
+use crate::state::persist_state;
+use crate::store;
+use crate::types::{ArchivedTask, Task, TaskEvent};
+
+/// Archived tasks returned by a search when no limit is given
+pub const DEFAULT_SEARCH_LIMIT: u32 = 100;
+/// Largest page a search may ask for
+pub const MAX_SEARCH_LIMIT: u32 = 1000;
+
+/// Result of asking for an archived task back
+#[derive(Debug)]
+pub enum Restore {
+    Restored(Task),
+    NotArchived,
+    /// A live task already uses the id, e.g. the agent was started again under it
+    AlreadyLive,
+}
+
+/// Move a task off the dashboard into the archive, dropping its debug data.
+/// Returns the archived task, or None when there is no such task.
+pub fn archive_task(ctx: &ApiContext, task_id: &str, now: i64) -> Result<Option<Task>, String> {
+    let archived = store::with_connection(|conn| {
+        let mut state = ctx.state.lock();
+        let Some(task) = state.tasks.get(task_id).cloned() else {
+            return Ok(None);
+        };
+        // Write the archive row first, so a failure leaves the task on the dashboard
+        store::archive_tasks(conn, std::slice::from_ref(&task), now)?;
+
+        state.tasks.remove(task_id);
+        state.debug_data.remove(task_id);
+        state.updated_at = now;
+        crate::notifications::forget_task(task_id);
+        crate::metrics::forget_task(task_id);
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskDeleted { revision, task_id: task_id.to_string() });
+        Ok(Some(task))
+    })?;
+
+    if archived.is_some() {
+        info!("Archived task: {task_id}");
+        ctx.events.state_changed();
+        if let Err(e) = persist_state(&ctx.state) {
+            error!("Failed to save app state: {e}");
+        }
+    }
+    Ok(archived)
+}
+
+/// Put an archived task back on the dashboard. Restoring counts as an update, so a finished
+/// task is not swept straight back out by the retention rules.
+pub fn restore_task(ctx: &ApiContext, task_id: &str, now: i64) -> Result<Restore, String> {
+    let restored = store::with_connection(|conn| {
+        let mut state = ctx.state.lock();
+        if state.tasks.contains_key(task_id) {
+            return Ok(Restore::AlreadyLive);
+        }
+        let Some(archived) = store::take_archived(conn, task_id)? else {
+            return Ok(Restore::NotArchived);
+        };
+
+        let mut task = archived.task;
+        task.updated_at = now;
+        state.tasks.insert(task.id.clone(), task.clone());
+        state.updated_at = now;
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskCreated { revision, task: task.clone() });
+        Ok(Restore::Restored(task))
+    })?;
+
+    if matches!(restored, Restore::Restored(_)) {
+        info!("Restored task from archive: {task_id}");
+        ctx.events.state_changed();
+        if let Err(e) = persist_state(&ctx.state) {
+            error!("Failed to save app state: {e}");
+        }
+    }
+    Ok(restored)
+}
+
+/// Search the archive; an empty query lists the most recently archived tasks
+pub fn search(query: Option<&str>, limit: Option<u32>) -> Result<Vec<ArchivedTask>, String> {
+    let query = query.map(str::trim).unwrap_or_default();
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).min(MAX_SEARCH_LIMIT);
+    store::with_connection(|conn| store::search_archived(conn, query, limit))
+}
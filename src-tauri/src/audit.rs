@@ -0,0 +1,170 @@
+use std::{collections::HashMap, net::SocketAddr};
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use log::{error, warn};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use crate::auth::{identify, is_metrics_token};
+use crate::store;
+use crate::tokens::METRICS_TOKEN_NAME;
+use crate::types::{AuthAudit, AuthEvent, TokenUsage};
+use crate::utils::current_timestamp;
+
+/// Rejected requests kept in the audit log
+const KEEP_EVENTS: u32 = 1000;
+/// Rejected requests returned when no limit is given
+pub const DEFAULT_AUDIT_LIMIT: u32 = 100;
+/// Failed authentications a peer may make within `FAILURE_WINDOW_SECONDS`
+const MAX_FAILURES: u32 = 10;
+const FAILURE_WINDOW_SECONDS: i64 = 60;
+/// How long a peer over the limit is turned away without its token being checked
+const BLOCK_SECONDS: i64 = 60;
+
+// Recent failed authentications of one peer
+#[derive(Debug, Default)]
+struct PeerFailures {
+    window_start: i64,
+    count: u32,
+    blocked_until: Option<i64>,
+}
+
+static FAILURES: Lazy<Mutex<HashMap<String, PeerFailures>>> = Lazy::new(Default::default);
+static USAGE: Lazy<Mutex<HashMap<String, TokenUsage>>> = Lazy::new(Default::default);
+
+/// Whether the peer is currently held back by the rate limiter
+fn is_blocked(peer: &str, now: i64) -> bool {
+    FAILURES.lock()
+        .get(peer)
+        .and_then(|failures| failures.blocked_until)
+        .is_some_and(|until| until > now)
+}
+
+/// Count a failed authentication. Returns true when it puts the peer over the limit.
+fn record_failure(peer: &str, now: i64) -> bool {
+    let mut all = FAILURES.lock();
+    let failures = all.entry(peer.to_string()).or_default();
+    if now - failures.window_start >= FAILURE_WINDOW_SECONDS {
+        *failures = PeerFailures { window_start: now, ..Default::default() };
+    }
+    failures.count += 1;
+    if failures.count < MAX_FAILURES {
+        return false;
+    }
+    failures.blocked_until = Some(now + BLOCK_SECONDS);
+    failures.count = 0;
+    failures.window_start = now;
+    true
+}
+
+fn record_usage(token: &str, route: &str, peer: Option<&str>, allowed: bool, now: i64) {
+    let mut usage = USAGE.lock();
+    let entry = usage.entry(token.to_string()).or_insert_with(|| TokenUsage {
+        token: token.to_string(),
+        requests: 0,
+        rejected: 0,
+        last_used_at: now,
+        last_route: String::new(),
+        last_peer: None,
+    });
+    if allowed {
+        entry.requests += 1;
+    } else {
+        entry.rejected += 1;
+    }
+    entry.last_used_at = now;
+    entry.last_route = route.to_string();
+    entry.last_peer = peer.map(str::to_string);
+}
+
+fn log_event(event: AuthEvent) {
+    warn!(
+        "Rejected {} {} from {} with {}",
+        event.method,
+        event.route,
+        event.peer.as_deref().unwrap_or("unix socket"),
+        event.status
+    );
+    if let Err(e) = store::with_connection(|conn| store::insert_auth_event(conn, &event, KEEP_EVENTS)) {
+        error!("{e}");
+    }
+}
+
+/// Middleware recording who uses the API: rejected requests go to the audit log, accepted
+/// ones to per-token usage. Peers that keep failing authentication are turned away with
+/// 429 for a while, whatever token they send, so a blocked peer cannot tell a right guess.
+/// Connections over the Unix socket have no peer address and are not rate limited.
+pub async fn track(request: Request, next: Next) -> Response {
+    let now = current_timestamp();
+    let peer = request.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip().to_string());
+    let method = request.method().to_string();
+    let route = request.uri().path().to_string();
+    let token = identify(request.headers())
+        .map(|(name, _)| name)
+        .or_else(|| is_metrics_token(request.headers()).then(|| METRICS_TOKEN_NAME.to_string()));
+
+    let event = |status: StatusCode, token: Option<String>| AuthEvent {
+        timestamp: now,
+        method: method.clone(),
+        route: route.clone(),
+        peer: peer.clone(),
+        status: status.as_u16(),
+        token,
+    };
+
+    if peer.as_deref().is_some_and(|address| is_blocked(address, now)) {
+        return StatusCode::TOO_MANY_REQUESTS.into_response();
+    }
+
+    let response = next.run(request).await;
+    let status = response.status();
+    match (status, &token) {
+        (StatusCode::UNAUTHORIZED, _) => {
+            log_event(event(status, None));
+            if let Some(address) = &peer {
+                if record_failure(address, now) {
+                    warn!("Too many failed authentications from {address}; blocking for {BLOCK_SECONDS}s");
+                    log_event(event(StatusCode::TOO_MANY_REQUESTS, None));
+                }
+            }
+        }
+        (StatusCode::FORBIDDEN, Some(name)) => {
+            log_event(event(status, Some(name.clone())));
+            record_usage(name, &route, peer.as_deref(), false, now);
+        }
+        (_, Some(name)) => record_usage(name, &route, peer.as_deref(), true, now),
+        _ => {}
+    }
+    response
+}
+
+/// Lift every block now instead of when it runs out
+pub fn expire_blocks() {
+    for failures in FAILURES.lock().values_mut() {
+        failures.blocked_until = None;
+    }
+}
+
+/// Recent rejected requests, how each token has been used and which peers are blocked
+pub fn report(limit: Option<u32>) -> Result<AuthAudit, String> {
+    let limit = limit.unwrap_or(DEFAULT_AUDIT_LIMIT).min(KEEP_EVENTS);
+    let rejected = store::with_connection(|conn| store::load_auth_events(conn, limit))?;
+
+    let mut tokens: Vec<TokenUsage> = USAGE.lock().values().cloned().collect();
+    tokens.sort_by(|a, b| b.last_used_at.cmp(&a.last_used_at).then_with(|| a.token.cmp(&b.token)));
+
+    let now = current_timestamp();
+    let mut blocked_peers: Vec<String> = FAILURES.lock()
+        .iter()
+        .filter(|(_, failures)| failures.blocked_until.is_some_and(|until| until > now))
+        .map(|(peer, _)| peer.clone())
+        .collect();
+    blocked_peers.sort();
+
+    Ok(AuthAudit { rejected, tokens, blocked_peers })
+}
@@ -1,7 +1,8 @@
 use std::{fs, sync::Arc};
 use parking_lot::Mutex;
 use once_cell::sync::Lazy;
-use axum::http::HeaderMap;
+use axum::http::{HeaderMap, StatusCode};
+use crate::types::TokenScope;
 use crate::utils::get_app_data_dir;
 
 #[cfg(unix)]
@@ -11,7 +12,7 @@ use std::os::unix::fs::PermissionsExt;
 pub static AUTH_TOKEN: Lazy<Arc<Mutex<Option<String>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
 
 /// Generate a cryptographically secure random token
-fn generate_secure_token() -> String {
+pub fn generate_secure_token() -> String {
     use rand::Rng;
     let mut rng = rand::thread_rng();
     let bytes: Vec<u8> = (0..32).map(|_| rng.gen()).collect();
@@ -72,28 +73,100 @@ pub fn get_or_create_auth_token() -> Result<String, String> {
 }
 
 /// Get path to auth token file
-fn get_auth_token_file_path() -> Result<std::path::PathBuf, String> {
+pub fn get_auth_token_file_path() -> Result<std::path::PathBuf, String> {
     let app_data_dir = get_app_data_dir()?;
     Ok(app_data_dir.join("auth.token"))
 }
 
-/// Authentication validation function with constant-time comparison
-pub fn validate_auth_header(headers: &HeaderMap) -> bool {
-    // Get the expected token
-    let expected_token = match get_or_create_auth_token() {
+/// Extract the token from a `Authorization: Bearer <token>` header
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Constant-time token comparison to prevent timing attacks
+pub fn tokens_match(token: &str, expected: &str) -> bool {
+    token.len() == expected.len()
+        && token.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// What an endpoint needs from the caller's token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Any valid token, e.g. health checks and setup status
+    Any,
+    /// Reporting task state
+    Report,
+    /// Reading state, history, analytics and the event stream
+    Read,
+    /// Removing or changing tasks, reading debug buffers and managing tokens
+    Admin,
+}
+
+impl TokenScope {
+    pub fn allows(&self, access: Access) -> bool {
+        matches!(
+            (self, access),
+            (TokenScope::Admin, _) | (_, Access::Any) | (TokenScope::Report, Access::Report) | (TokenScope::Read, Access::Read)
+        )
+    }
+}
+
+/// Name and scope of the bearer token: the primary token is admin, named tokens carry their own scope
+pub fn identify(headers: &HeaderMap) -> Option<(String, TokenScope)> {
+    let token = bearer_token(headers)?;
+    // Fail closed if we can't get the primary token
+    let primary = get_or_create_auth_token().ok()?;
+    if tokens_match(token, &primary) {
+        return Some((crate::tokens::PRIMARY_TOKEN_NAME.to_string(), TokenScope::Admin));
+    }
+    crate::tokens::find(token)
+}
+
+/// Check the bearer token against what the endpoint needs:
+/// 401 without a valid token, 403 when its scope does not cover the request
+pub fn authorize(headers: &HeaderMap, access: Access) -> Result<(), StatusCode> {
+    match identify(headers).map(|(_, scope)| scope) {
+        Some(scope) if scope.allows(access) => Ok(()),
+        Some(_) => Err(StatusCode::FORBIDDEN),
+        None => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+// Optional read-only token for metrics scrapers (loaded once, on first use)
+static METRICS_TOKEN: Lazy<Option<String>> = Lazy::new(|| {
+    let token = match std::env::var("TALLR_METRICS_TOKEN") {
         Ok(token) => token,
-        Err(_) => return false, // Fail closed if we can't get a token
-    };
-    
-    // Check if Authorization header exists and matches
-    if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                // Use constant-time comparison to prevent timing attacks
-                return token.len() == expected_token.len() 
-                    && token.bytes().zip(expected_token.bytes()).all(|(a, b)| a == b);
-            }
+        Err(_) => {
+            let token_file = get_app_data_dir().ok()?.join("metrics.token");
+            fs::read_to_string(token_file).ok()?
         }
+    };
+    let token = token.trim().to_string();
+    (!token.is_empty()).then_some(token)
+});
+
+/// Optional read-only token for metrics scrapers, from `TALLR_METRICS_TOKEN` or `metrics.token`.
+/// Unlike the main token it is never generated; scraping with it is opt-in.
+pub fn get_metrics_token() -> Option<&'static str> {
+    METRICS_TOKEN.as_deref()
+}
+
+/// Whether the bearer token is the read-only metrics token
+pub fn is_metrics_token(headers: &HeaderMap) -> bool {
+    match (get_metrics_token(), bearer_token(headers)) {
+        (Some(expected), Some(token)) => tokens_match(token, expected),
+        _ => false,
+    }
+}
+
+/// Accept any token with read access, or the read-only metrics token
+pub fn authorize_metrics(headers: &HeaderMap) -> Result<(), StatusCode> {
+    match authorize(headers, Access::Read) {
+        Err(_) if is_metrics_token(headers) => Ok(()),
+        result => result,
     }
-    false
-}
\ No newline at end of file
+}
@@ -0,0 +1,450 @@
+// `tallrctl` - query and control Tallr sessions from the terminal, using the same
+// token and endpoint discovery as the Node wrapper
+
+use std::{collections::HashMap, process::ExitCode, time::Duration};
+use chrono::Local;
+use clap::{Parser, Subcommand};
+use serde_json::{json, Value};
+use tallr_lib::client::TallrClient;
+use tallr_lib::types::{ApiToken, AppState, ArchivedTask, IssuedToken, Task, TaskEvent, TaskState, TokenScope};
+
+mod tui;
+
+#[derive(Parser)]
+#[command(name = "tallrctl", version, about = "Query and control Tallr sessions")]
+struct Cli {
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List sessions
+    Ls {
+        /// Only show sessions in these states (repeatable)
+        #[arg(long, short)]
+        state: Vec<TaskState>,
+    },
+    /// Stream session changes as they happen
+    Watch,
+    /// Mark a session as done
+    Done {
+        /// Session id (or unique prefix)
+        id: String,
+    },
+    /// Pin a session to the top of the dashboard
+    Pin {
+        /// Session id (or unique prefix)
+        id: String,
+    },
+    /// Unpin a session
+    Unpin {
+        /// Session id (or unique prefix)
+        id: String,
+    },
+    /// Silence a session's notifications for a while
+    Snooze {
+        /// Session id (or unique prefix)
+        id: String,
+        /// Minutes to snooze for; 0 lifts the snooze
+        #[arg(default_value_t = 30)]
+        minutes: u64,
+    },
+    /// Remove sessions from the dashboard by id or by state; they are kept in the archive
+    Rm {
+        /// Session ids (or unique prefixes)
+        ids: Vec<String>,
+        /// Remove every session in these states (repeatable)
+        #[arg(long, short)]
+        state: Vec<TaskState>,
+    },
+    /// Search archived sessions by title, details, agent, id or project
+    Archive {
+        /// Text to search for; lists the most recently archived sessions when omitted
+        query: Option<String>,
+        /// Maximum number of sessions to list
+        #[arg(long, short, default_value_t = 20)]
+        limit: u32,
+    },
+    /// Put an archived session back on the dashboard
+    Restore {
+        /// Archived session id
+        id: String,
+    },
+    /// Print the aggregate state, e.g. for a shell prompt
+    Status,
+    /// Manage named API tokens (needs the primary or an admin token)
+    Token {
+        #[command(subcommand)]
+        command: TokenCommand,
+    },
+    /// Interactive dashboard mirroring the desktop window
+    Tui,
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// List named tokens
+    Ls,
+    /// Create a token and print its secret, which is shown only once
+    Create {
+        name: String,
+        /// report (task updates from wrappers), read (dashboards, metrics) or admin
+        #[arg(long, short, default_value_t = TokenScope::Report)]
+        scope: TokenScope,
+    },
+    /// Issue a new secret for a token; the old one stops working at once
+    Rotate {
+        name: String,
+    },
+    /// Revoke a token
+    Revoke {
+        name: String,
+    },
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match TallrClient::from_env() {
+        Ok(client) => run(&client, cli.command, cli.json).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("tallrctl: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(client: &TallrClient, command: Command, json_output: bool) -> Result<(), String> {
+    match command {
+        Command::Ls { state } => {
+            let app_state: AppState = client.get("/v1/state").await?;
+            let mut tasks: Vec<&Task> = app_state.tasks.values()
+                .filter(|task| state.is_empty() || state.contains(&task.state))
+                .collect();
+            // Pinned first, then most recently updated
+            tasks.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.updated_at.cmp(&a.updated_at)));
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&tasks).unwrap_or_default());
+            } else {
+                print_tasks(&app_state, &tasks);
+            }
+            Ok(())
+        }
+        Command::Watch => watch(client, json_output).await,
+        Command::Tui => tui::run(client.clone()).await,
+        Command::Done { id } => {
+            let task = find_task(client, &id).await?;
+            client.post("/v1/tasks/done", json!({ "taskId": task.id, "source": "cli" })).await?;
+            println!("Marked {} as done", task.id);
+            Ok(())
+        }
+        Command::Pin { id } => set_pinned(client, &id, true).await,
+        Command::Unpin { id } => set_pinned(client, &id, false).await,
+        Command::Snooze { id, minutes } => {
+            let task = find_task(client, &id).await?;
+            client.post("/v1/tasks/snooze", json!({ "taskId": task.id, "minutes": minutes })).await?;
+            if minutes == 0 {
+                println!("Notifications back on for {}", task.id);
+            } else {
+                println!("Snoozed {} for {minutes} min", task.id);
+            }
+            Ok(())
+        }
+        Command::Rm { ids, state } => {
+            if ids.is_empty() && state.is_empty() {
+                return Err("Give session ids or --state to choose what to remove".to_string());
+            }
+
+            let app_state: AppState = client.get("/v1/state").await?;
+            let mut targets: Vec<String> = app_state.tasks.values()
+                .filter(|task| state.contains(&task.state))
+                .map(|task| task.id.clone())
+                .collect();
+            for id in &ids {
+                targets.push(resolve_task(&app_state, id)?.id.clone());
+            }
+            targets.sort();
+            targets.dedup();
+
+            for task_id in &targets {
+                client.post("/v1/tasks/delete", json!({ "taskId": task_id })).await?;
+                if !json_output {
+                    println!("Archived {task_id}");
+                }
+            }
+            if json_output {
+                println!("{}", json!({ "archived": targets }));
+            }
+            Ok(())
+        }
+        Command::Archive { query, limit } => {
+            let mut path = format!("/v1/archive?limit={limit}");
+            if let Some(query) = &query {
+                path.push_str(&format!("&q={}", query_escape(query)));
+            }
+            let archived: Vec<ArchivedTask> = client.get(&path).await?;
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&archived).unwrap_or_default());
+            } else {
+                print_archived(&archived);
+            }
+            Ok(())
+        }
+        Command::Restore { id } => {
+            client.post("/v1/tasks/restore", json!({ "taskId": id })).await?;
+            println!("Restored {id}");
+            Ok(())
+        }
+        Command::Token { command } => token(client, command, json_output).await,
+        Command::Status => {
+            let app_state: AppState = client.get("/v1/state").await?;
+            let aggregate = TaskState::aggregate(app_state.tasks.values().map(|task| task.state));
+
+            if json_output {
+                let mut counts: HashMap<TaskState, usize> = HashMap::new();
+                for task in app_state.tasks.values() {
+                    *counts.entry(task.state).or_default() += 1;
+                }
+                println!("{}", json!({ "state": aggregate, "counts": counts }));
+            } else {
+                println!("{aggregate}");
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn token(client: &TallrClient, command: TokenCommand, json_output: bool) -> Result<(), String> {
+    let issued: IssuedToken = match command {
+        TokenCommand::Ls => {
+            let tokens: Vec<ApiToken> = client.get("/v1/tokens").await?;
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&tokens).unwrap_or_default());
+            } else if tokens.is_empty() {
+                println!("No named tokens");
+            } else {
+                for token in &tokens {
+                    let rotated = token.rotated_at.map(|at| format!(", rotated {} ago", age(at))).unwrap_or_default();
+                    println!("{:<24} {:<7} created {} ago{rotated}", token.name, token.scope, age(token.created_at));
+                }
+            }
+            return Ok(());
+        }
+        TokenCommand::Create { name, scope } => {
+            client.post_json("/v1/tokens", json!({ "name": name, "scope": scope })).await?
+        }
+        TokenCommand::Rotate { name } => client.post_json("/v1/tokens/rotate", json!({ "name": name })).await?,
+        TokenCommand::Revoke { name } => {
+            client.post("/v1/tokens/revoke", json!({ "name": name })).await?;
+            println!("Revoked {name}");
+            return Ok(());
+        }
+    };
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&issued).unwrap_or_default());
+    } else {
+        println!("{} token '{}': {}", issued.token.scope, issued.token.name, issued.secret);
+        eprintln!("Store it now; it cannot be shown again.");
+    }
+    Ok(())
+}
+
+async fn set_pinned(client: &TallrClient, id: &str, pinned: bool) -> Result<(), String> {
+    let task = find_task(client, id).await?;
+    client.post("/v1/tasks/pin", json!({ "taskId": task.id, "pinned": pinned })).await?;
+    println!("{} {}", if pinned { "Pinned" } else { "Unpinned" }, task.id);
+    Ok(())
+}
+
+async fn find_task(client: &TallrClient, id: &str) -> Result<Task, String> {
+    let app_state: AppState = client.get("/v1/state").await?;
+    resolve_task(&app_state, id).cloned()
+}
+
+/// Match a session by exact id or unique prefix
+fn resolve_task<'a>(app_state: &'a AppState, id: &str) -> Result<&'a Task, String> {
+    if let Some(task) = app_state.tasks.get(id) {
+        return Ok(task);
+    }
+    let matches: Vec<&Task> = app_state.tasks.values().filter(|task| task.id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [task] => Ok(task),
+        [] => Err(format!("No session matches '{id}'")),
+        _ => Err(format!("'{id}' matches {} sessions; use a longer prefix", matches.len())),
+    }
+}
+
+/// Seconds since `timestamp` as a short human duration
+fn age(timestamp: i64) -> String {
+    let seconds = (chrono::Utc::now().timestamp() - timestamp).max(0);
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn print_tasks(app_state: &AppState, tasks: &[&Task]) {
+    if tasks.is_empty() {
+        println!("No sessions");
+        return;
+    }
+
+    let rows = tasks.iter().map(|task| {
+        let project = app_state.projects.get(&task.project_id)
+            .map(|project| project.name.clone())
+            .unwrap_or_default();
+        [
+            format!("{}{}", if task.pinned { "*" } else { "" }, task.id),
+            task.state.to_string(),
+            task.agent.clone(),
+            truncate(&project, 24),
+            truncate(&task.title, 40),
+            age(task.updated_at),
+        ]
+    }).collect();
+    print_table(["ID", "STATE", "AGENT", "PROJECT", "TITLE", "UPDATED"], rows);
+}
+
+fn print_archived(archived: &[ArchivedTask]) {
+    if archived.is_empty() {
+        println!("No archived sessions");
+        return;
+    }
+
+    let rows = archived.iter().map(|entry| {
+        let task = &entry.task;
+        [
+            task.id.clone(),
+            task.state.to_string(),
+            task.agent.clone(),
+            truncate(entry.project_name.as_deref().unwrap_or(&task.project_id), 24),
+            truncate(&task.title, 40),
+            age(entry.archived_at),
+        ]
+    }).collect();
+    print_table(["ID", "STATE", "AGENT", "PROJECT", "TITLE", "ARCHIVED"], rows);
+}
+
+fn print_table(header: [&str; 6], rows: Vec<[String; 6]>) {
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: &[String]| {
+        let line: Vec<String> = cells.iter().zip(widths).map(|(cell, width)| format!("{cell:<width$}")).collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+    print_row(&header.map(str::to_string));
+    for row in &rows {
+        print_row(row);
+    }
+}
+
+/// Percent-encode a query string value
+fn query_escape(value: &str) -> String {
+    value.bytes().map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+        _ => format!("%{byte:02X}"),
+    }).collect()
+}
+
+/// Follow `/v1/events`, reconnecting (and resuming) if the app restarts
+async fn watch(client: &TallrClient, json_output: bool) -> Result<(), String> {
+    let mut titles: HashMap<String, String> = client.get::<AppState>("/v1/state").await?
+        .tasks.into_values()
+        .map(|task| (task.id, task.title))
+        .collect();
+    let mut last_event_id = None;
+
+    loop {
+        let mut stream = match client.events(last_event_id).await {
+            Ok(stream) => stream,
+            Err(e) if last_event_id.is_some() => {
+                eprintln!("tallrctl: {e}; retrying");
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        while let Some(event) = stream.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    eprintln!("tallrctl: {e}");
+                    break;
+                }
+            };
+            last_event_id = event.id.or(last_event_id);
+
+            if json_output {
+                println!("{}", event.data);
+                continue;
+            }
+            match serde_json::from_str::<TaskEvent>(&event.data) {
+                Ok(task_event) => print_event(&task_event, &mut titles),
+                Err(_) => println!("{} {}", event.event, event.data),
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+fn print_event(event: &TaskEvent, titles: &mut HashMap<String, String>) {
+    let time = Local::now().format("%H:%M:%S");
+
+    let line = match event {
+        TaskEvent::TaskCreated { task, .. } => {
+            titles.insert(task.id.clone(), task.title.clone());
+            format!("created  {}  {}  \"{}\"", task.id, task.state, task.title)
+        }
+        TaskEvent::TaskUpdated { task_id, changes, .. } => {
+            let summary: Vec<String> = changes.iter()
+                .filter(|(field, _)| field.as_str() != "updatedAt")
+                .map(|(field, value)| match value {
+                    Value::String(text) => format!("{field}={}", truncate(text, 40)),
+                    other => format!("{field}={other}"),
+                })
+                .collect();
+            let title = titles.get(task_id).map(String::as_str).unwrap_or("");
+            format!("updated  {task_id}  {}  \"{title}\"", summary.join(" "))
+        }
+        TaskEvent::TaskDeleted { task_id, .. } => {
+            titles.remove(task_id);
+            format!("deleted  {task_id}")
+        }
+        TaskEvent::ProjectUpdated { project, .. } => {
+            format!("project  {}  {}", project.name, project.repo_path)
+        }
+    };
+    println!("{time}  {line}");
+}
@@ -0,0 +1,600 @@
+use std::{
+    process::{Command, Stdio},
+    time::Duration,
+};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tallr_lib::client::TallrClient;
+use tallr_lib::desktop::{launch_path, OPEN_COMMAND};
+use tallr_lib::types::{AppState, Project, Task, TaskEvent, TaskState};
+use super::{age, truncate};
+
+/// Minutes the `s` key silences a session's notifications for
+const SNOOZE_MINUTES: u64 = 30;
+
+enum Message {
+    Input(Event),
+    Change(TaskEvent),
+    Connected,
+    Disconnected(String),
+}
+
+/// Sort order of the desktop list (see TASK_STATE_PRIORITY in the frontend)
+fn state_priority(state: TaskState) -> u8 {
+    match state {
+        TaskState::Pending => 0,
+        TaskState::Working => 1,
+        TaskState::Idle => 2,
+        TaskState::Done => 3,
+        TaskState::Error => 4,
+        TaskState::Cancelled => 5,
+        TaskState::Stale => 6,
+    }
+}
+
+fn state_color(state: TaskState) -> Color {
+    match state {
+        TaskState::Pending => Color::Yellow,
+        TaskState::Working => Color::Blue,
+        TaskState::Idle => Color::Gray,
+        TaskState::Error => Color::Red,
+        TaskState::Done => Color::Green,
+        TaskState::Cancelled => Color::DarkGray,
+        TaskState::Stale => Color::Magenta,
+    }
+}
+
+fn state_badge(state: TaskState) -> Span<'static> {
+    Span::styled(
+        format!(" {state} "),
+        Style::new().fg(Color::Black).bg(state_color(state)).add_modifier(Modifier::BOLD),
+    )
+}
+
+/// Run the dashboard until the user quits
+pub async fn run(client: TallrClient) -> Result<(), String> {
+    let state: AppState = client.get("/v1/state").await?;
+    let (sender, mut receiver) = mpsc::channel(64);
+    spawn_input_reader(sender.clone());
+    spawn_event_stream(client.clone(), state.revision, sender);
+
+    let mut terminal = ratatui::try_init().map_err(|e| format!("Failed to start terminal UI: {e}"))?;
+    let mut app = App::new(client, state);
+    let result = app.run(&mut terminal, &mut receiver).await;
+    ratatui::restore();
+    result
+}
+
+/// Terminal input is blocking, so read it on its own thread
+fn spawn_input_reader(sender: mpsc::Sender<Message>) {
+    std::thread::spawn(move || {
+        while let Ok(input) = event::read() {
+            if sender.blocking_send(Message::Input(input)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Follow the event stream from `revision`, reconnecting if the app restarts
+fn spawn_event_stream(client: TallrClient, revision: u64, sender: mpsc::Sender<Message>) {
+    tokio::spawn(async move {
+        let mut last_event_id = Some(revision);
+        loop {
+            let reason = match client.events(last_event_id).await {
+                Ok(mut stream) => {
+                    let _ = sender.send(Message::Connected).await;
+                    let mut reason = "Event stream closed".to_string();
+                    while let Some(event) = stream.next().await {
+                        let event = match event {
+                            Ok(event) => event,
+                            Err(e) => {
+                                reason = e;
+                                break;
+                            }
+                        };
+                        last_event_id = event.id.or(last_event_id);
+                        if let Ok(change) = serde_json::from_str::<TaskEvent>(&event.data) {
+                            if sender.send(Message::Change(change)).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    reason
+                }
+                Err(e) => e,
+            };
+
+            if sender.send(Message::Disconnected(reason)).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    });
+}
+
+/// Open the project in its preferred IDE, falling back to the desktop's default handler
+fn open_project(project: &Project) -> Result<String, String> {
+    let mut programs = Vec::new();
+    if !project.preferred_ide.is_empty() {
+        programs.push(project.preferred_ide.as_str());
+    }
+    programs.push(OPEN_COMMAND);
+
+    let mut errors = Vec::new();
+    for program in programs {
+        let spawned = Command::new(program)
+            .arg(&project.repo_path)
+            .env("PATH", launch_path())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(_) => return Ok(program.to_string()),
+            Err(e) => errors.push(format!("{program}: {e}")),
+        }
+    }
+    Err(format!("Could not open {}: {}", project.name, errors.join(", ")))
+}
+
+struct App {
+    client: TallrClient,
+    state: AppState,
+    show_done: bool,
+    project_filter: Option<String>,
+    selected_id: Option<String>,
+    selected_index: usize,
+    confirm_delete: Option<String>,
+    status: Option<String>,
+    connected: bool,
+    quit: bool,
+}
+
+impl App {
+    fn new(client: TallrClient, state: AppState) -> Self {
+        let mut app = Self {
+            client,
+            state,
+            show_done: false,
+            project_filter: None,
+            selected_id: None,
+            selected_index: 0,
+            confirm_delete: None,
+            status: None,
+            connected: false,
+            quit: false,
+        };
+        app.sync_selection();
+        app
+    }
+
+    async fn run(&mut self, terminal: &mut DefaultTerminal, receiver: &mut mpsc::Receiver<Message>) -> Result<(), String> {
+        // Redraw periodically so ages stay current
+        let mut tick = tokio::time::interval(Duration::from_secs(5));
+
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame)).map_err(|e| format!("Failed to draw: {e}"))?;
+            tokio::select! {
+                Some(message) = receiver.recv() => self.handle(message).await,
+                _ = tick.tick() => {}
+            }
+            self.sync_selection();
+        }
+        Ok(())
+    }
+
+    async fn handle(&mut self, message: Message) {
+        match message {
+            Message::Input(Event::Key(key)) if key.kind == KeyEventKind::Press => self.handle_key(key).await,
+            Message::Input(_) => {}
+            Message::Change(change) => {
+                // Replayed events we already have are skipped; a gap means we missed some
+                if !self.state.apply_event(&change) && change.revision() > self.state.revision {
+                    self.resync().await;
+                }
+            }
+            Message::Connected => {
+                self.connected = true;
+                // Catch up on anything that changed while disconnected
+                self.resync().await;
+            }
+            Message::Disconnected(reason) => {
+                if self.connected {
+                    self.status = Some(format!("Disconnected: {reason}. Reconnecting..."));
+                }
+                self.connected = false;
+            }
+        }
+    }
+
+    async fn resync(&mut self) {
+        match self.client.get::<AppState>("/v1/state").await {
+            Ok(state) => self.state = state,
+            Err(e) => self.status = Some(e),
+        }
+    }
+
+    async fn handle_key(&mut self, key: KeyEvent) {
+        if let Some(task_id) = self.confirm_delete.take() {
+            self.status = if key.code == KeyCode::Char('y') {
+                Some(self.action("/v1/tasks/delete", json!({ "taskId": task_id }), "Archived").await)
+            } else {
+                None
+            };
+            return;
+        }
+        self.status = None;
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Tab => self.cycle_project(1),
+            KeyCode::BackTab => self.cycle_project(-1),
+            KeyCode::Char('a') => {
+                self.show_done = !self.show_done;
+                self.project_filter = None;
+            }
+            KeyCode::Char('r') => self.resync().await,
+            _ => self.handle_task_key(key.code).await,
+        }
+    }
+
+    /// Keys acting on the selected session
+    async fn handle_task_key(&mut self, code: KeyCode) {
+        let Some(task) = self.selected_task().cloned() else { return };
+
+        self.status = match code {
+            KeyCode::Char('p') => {
+                let body = json!({ "taskId": task.id, "pinned": !task.pinned });
+                Some(self.action("/v1/tasks/pin", body, if task.pinned { "Unpinned" } else { "Pinned" }).await)
+            }
+            KeyCode::Char('s') => {
+                let body = json!({ "taskId": task.id, "minutes": SNOOZE_MINUTES });
+                Some(self.action("/v1/tasks/snooze", body, &format!("Snoozed for {SNOOZE_MINUTES} min")).await)
+            }
+            KeyCode::Char('d') => {
+                let body = json!({ "taskId": task.id, "source": "tui" });
+                Some(self.action("/v1/tasks/done", body, "Marked as done").await)
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                self.confirm_delete = Some(task.id.clone());
+                Some(format!("Archive \"{}\"? (y/n)", truncate(&task.title, 40)))
+            }
+            KeyCode::Enter | KeyCode::Char('o') => match self.state.projects.get(&task.project_id) {
+                Some(project) => Some(match open_project(project) {
+                    Ok(program) => format!("Opened {} with {program}", project.name),
+                    Err(e) => e,
+                }),
+                None => Some("Session has no project".to_string()),
+            },
+            _ => None,
+        };
+    }
+
+    async fn action(&self, path: &str, body: serde_json::Value, done: &str) -> String {
+        let task_id = body["taskId"].as_str().unwrap_or_default().to_string();
+        match self.client.post(path, body).await {
+            Ok(()) => format!("{done}: {task_id}"),
+            Err(e) => e,
+        }
+    }
+
+    /// Tasks in display order: the desktop's sort (pinned, state priority, age), grouped by project
+    fn visible_tasks(&self) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.state.tasks.values()
+            .filter(|task| (task.state == TaskState::Done) == self.show_done)
+            .filter(|task| self.project_filter.as_ref().is_none_or(|id| &task.project_id == id))
+            .collect();
+        tasks.sort_by(|a, b| {
+            b.pinned.cmp(&a.pinned)
+                .then(state_priority(a.state).cmp(&state_priority(b.state)))
+                .then(a.created_at.cmp(&b.created_at))
+                .then(a.id.cmp(&b.id))
+        });
+
+        // Projects keep the position of their highest ranked task
+        let mut project_order: Vec<&str> = Vec::new();
+        for task in &tasks {
+            if !project_order.contains(&task.project_id.as_str()) {
+                project_order.push(&task.project_id);
+            }
+        }
+        project_order.iter()
+            .flat_map(|project_id| tasks.iter().copied().filter(move |task| task.project_id == *project_id))
+            .collect()
+    }
+
+    /// Projects with sessions in the current (active or done) view, by name
+    fn filter_projects(&self) -> Vec<&Project> {
+        let mut projects: Vec<&Project> = self.state.projects.values()
+            .filter(|project| self.state.tasks.values().any(|task| {
+                task.project_id == project.id && (task.state == TaskState::Done) == self.show_done
+            }))
+            .collect();
+        projects.sort_by_key(|project| project.name.to_lowercase());
+        projects
+    }
+
+    fn selected_task(&self) -> Option<&Task> {
+        self.selected_id.as_ref().and_then(|id| self.state.tasks.get(id))
+    }
+
+    /// Keep the selection on the same session, or on its neighbour once it disappears
+    fn sync_selection(&mut self) {
+        let visible: Vec<String> = self.visible_tasks().iter().map(|task| task.id.clone()).collect();
+        if let Some(index) = self.selected_id.as_ref().and_then(|id| visible.iter().position(|v| v == id)) {
+            self.selected_index = index;
+            return;
+        }
+        self.selected_index = self.selected_index.min(visible.len().saturating_sub(1));
+        self.selected_id = visible.get(self.selected_index).cloned();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let visible = self.visible_tasks();
+        if visible.is_empty() {
+            return;
+        }
+        let index = self.selected_index.saturating_add_signed(delta).min(visible.len() - 1);
+        self.selected_id = Some(visible[index].id.clone());
+        self.selected_index = index;
+    }
+
+    fn cycle_project(&mut self, delta: isize) {
+        let projects: Vec<String> = self.filter_projects().iter().map(|project| project.id.clone()).collect();
+        // Position 0 is "All"
+        let current = self.project_filter.as_ref()
+            .and_then(|id| projects.iter().position(|p| p == id))
+            .map_or(0, |index| index + 1);
+        let count = projects.len() as isize + 1;
+        let next = (current as isize + delta).rem_euclid(count) as usize;
+        self.project_filter = next.checked_sub(1).map(|index| projects[index].clone());
+        self.selected_id = None;
+        self.selected_index = 0;
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header_area, filter_area, list_area, detail_area, footer_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(self.header(), header_area);
+        frame.render_widget(self.project_filters(), filter_area);
+
+        let (items, selected) = self.list_items();
+        let title = if self.show_done { " Done sessions " } else { " Active sessions " };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut ListState::default().with_selected(selected));
+
+        frame.render_widget(self.details(), detail_area);
+        frame.render_widget(self.footer(), footer_area);
+    }
+
+    fn header(&self) -> Line<'_> {
+        let tasks = self.state.tasks.values();
+        let done = tasks.clone().filter(|task| task.state == TaskState::Done).count();
+        let active = self.state.tasks.len() - done;
+        let aggregate = TaskState::aggregate(self.state.tasks.values().map(|task| task.state));
+        let connection = if self.connected {
+            Span::styled("● live", Style::new().fg(Color::Green))
+        } else {
+            Span::styled("○ connecting", Style::new().fg(Color::DarkGray))
+        };
+
+        Line::from(vec![
+            Span::from(" Tallr ").bold(),
+            state_badge(aggregate),
+            Span::raw(format!("  {active} active · {done} done  ")),
+            connection,
+            Span::from(format!("  {}", self.client.endpoint)).dark_gray(),
+        ])
+    }
+
+    fn project_filters(&self) -> Line<'_> {
+        let highlight = |selected: bool| {
+            if selected { Style::new().fg(Color::Black).bg(Color::Cyan) } else { Style::new() }
+        };
+        let mut spans = vec![
+            Span::raw(" "),
+            Span::styled(" All ", highlight(self.project_filter.is_none())),
+        ];
+        for project in self.filter_projects() {
+            let count = self.state.tasks.values()
+                .filter(|task| task.project_id == project.id && (task.state == TaskState::Done) == self.show_done)
+                .count();
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                format!(" {} ({count}) ", project.name),
+                highlight(self.project_filter.as_ref() == Some(&project.id)),
+            ));
+        }
+        Line::from(spans)
+    }
+
+    /// List rows with project headings, and the row index of the selected session
+    fn list_items(&self) -> (Vec<ListItem<'_>>, Option<usize>) {
+        let mut items = Vec::new();
+        let mut selected = None;
+        let mut current_project: Option<&str> = None;
+
+        for task in self.visible_tasks() {
+            if current_project != Some(task.project_id.as_str()) {
+                current_project = Some(&task.project_id);
+                let heading = match self.state.projects.get(&task.project_id) {
+                    Some(project) => Line::from(vec![
+                        Span::from(project.name.clone()).bold().cyan(),
+                        Span::from(format!("  {}", project.repo_path)).dark_gray(),
+                    ]),
+                    None => Line::from(Span::from("Unknown project").bold()),
+                };
+                items.push(ListItem::new(heading));
+            }
+
+            if self.selected_id.as_deref() == Some(task.id.as_str()) {
+                selected = Some(items.len());
+            }
+            items.push(ListItem::new(Line::from(vec![
+                Span::raw(if task.pinned { " * " } else { "   " }).fg(Color::Cyan),
+                state_badge(task.state),
+                Span::from(format!(" {} ", task.agent)).bold(),
+                Span::raw(task.title.clone()),
+                Span::from(format!("  {}", age(task.updated_at))).dark_gray(),
+            ])));
+        }
+
+        if items.is_empty() {
+            let message = if self.show_done { "No finished sessions" } else { "No active sessions. Run `tallr claude` to start one." };
+            items.push(ListItem::new(Line::from(message).dark_gray()));
+        }
+        (items, selected)
+    }
+
+    fn details(&self) -> Paragraph<'_> {
+        let block = Block::bordered().title(" Details ");
+        let Some(task) = self.selected_task() else {
+            return Paragraph::new("").block(block);
+        };
+
+        let project = self.state.projects.get(&task.project_id);
+        let mut lines = vec![
+            Line::from(vec![
+                state_badge(task.state),
+                Span::from(format!(" {} · {}", task.agent, task.title)).bold(),
+            ]),
+            Line::from(format!(
+                "{}  ·  updated {} ago  ·  started {} ago{}",
+                project.map(|project| project.repo_path.as_str()).unwrap_or("unknown project"),
+                age(task.updated_at),
+                age(task.created_at),
+                task.detection_method.as_ref().map(|method| format!("  ·  via {method}")).unwrap_or_default(),
+            ))
+            .dark_gray(),
+        ];
+        if let Some(details) = task.details.as_ref().filter(|details| !details.is_empty()) {
+            lines.extend(details.lines().map(|line| Line::from(line.to_string())));
+        }
+
+        Paragraph::new(lines).block(block).wrap(Wrap { trim: false })
+    }
+
+    fn footer(&self) -> Line<'_> {
+        if let Some(status) = &self.status {
+            return Line::from(format!(" {status}")).yellow();
+        }
+        let view = if self.show_done { "active" } else { "done" };
+        Line::from(format!(
+            " ↑↓ move  p pin  s snooze  d done  x archive  enter open project  tab project  a {view}  r refresh  q quit"
+        ))
+        .dark_gray()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tallr_lib::client::Endpoint;
+
+    fn app(tasks: &[(&str, &str, TaskState, bool, i64)]) -> App {
+        let mut state = AppState::default();
+        for name in ["api", "web"] {
+            state.projects.insert(name.to_string(), Project {
+                id: name.to_string(),
+                name: name.to_string(),
+                repo_path: format!("/src/{name}"),
+                preferred_ide: "cursor".to_string(),
+                github_url: None,
+                created_at: 0,
+                updated_at: 0,
+            });
+        }
+        for &(id, project_id, task_state, pinned, created_at) in tasks {
+            state.tasks.insert(id.to_string(), Task {
+                id: id.to_string(),
+                project_id: project_id.to_string(),
+                agent: "claude".to_string(),
+                title: id.to_string(),
+                state: task_state,
+                details: None,
+                created_at,
+                updated_at: created_at,
+                pinned,
+                detection_method: None,
+                last_heartbeat: None,
+            });
+        }
+        let client = TallrClient::new(Endpoint::parse("127.0.0.1:4317"), String::new()).unwrap();
+        App::new(client, state)
+    }
+
+    fn visible(app: &App) -> Vec<&str> {
+        app.visible_tasks().iter().map(|task| task.id.as_str()).collect()
+    }
+
+    #[test]
+    fn sessions_are_sorted_like_the_desktop_and_grouped_by_project() {
+        let app = app(&[
+            ("web-working", "web", TaskState::Working, false, 1),
+            ("api-idle", "api", TaskState::Idle, false, 2),
+            ("web-pending", "web", TaskState::Pending, false, 3),
+            ("api-pinned", "api", TaskState::Idle, true, 4),
+            ("api-done", "api", TaskState::Done, false, 5),
+        ]);
+        assert_eq!(visible(&app), ["api-pinned", "api-idle", "web-pending", "web-working"]);
+    }
+
+    #[test]
+    fn the_done_view_and_project_filter_narrow_the_list() {
+        let mut app = app(&[
+            ("api-working", "api", TaskState::Working, false, 1),
+            ("web-working", "web", TaskState::Working, false, 2),
+            ("web-done", "web", TaskState::Done, false, 3),
+        ]);
+        app.cycle_project(1);
+        assert_eq!(app.project_filter.as_deref(), Some("api"));
+        assert_eq!(visible(&app), ["api-working"]);
+        app.cycle_project(-2);
+        assert_eq!(app.project_filter.as_deref(), Some("web"), "wraps around past All");
+
+        app.show_done = true;
+        app.project_filter = None;
+        assert_eq!(visible(&app), ["web-done"]);
+    }
+
+    #[test]
+    fn the_selection_follows_its_session_and_falls_back_to_a_neighbour() {
+        let mut app = app(&[
+            ("first", "api", TaskState::Pending, false, 1),
+            ("second", "api", TaskState::Working, false, 2),
+            ("third", "api", TaskState::Idle, false, 3),
+        ]);
+        app.move_selection(5);
+        assert_eq!(app.selected_id.as_deref(), Some("third"), "stops at the end");
+
+        // a session moving up the list stays selected
+        app.state.tasks.get_mut("third").unwrap().state = TaskState::Pending;
+        app.sync_selection();
+        assert_eq!((app.selected_id.as_deref(), app.selected_index), (Some("third"), 1));
+
+        app.state.tasks.remove("third");
+        app.sync_selection();
+        assert_eq!(app.selected_id.as_deref(), Some("second"));
+    }
+}
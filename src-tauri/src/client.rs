@@ -0,0 +1,256 @@
+use std::{collections::VecDeque, path::PathBuf};
+use axum::body::Bytes;
+use reqwest::{Method, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use crate::server::{get_discovery_file_path, DEFAULT_SERVER_ADDRESS};
+
+/// Where a running Tallr API can be reached
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    /// `host:port`
+    Tcp(String),
+    /// Path of a Unix domain socket
+    Unix(PathBuf),
+}
+
+impl Endpoint {
+    /// Parse `http://host:port`, `host:port` or `unix:/path/to.sock`
+    pub fn parse(gateway: &str) -> Self {
+        if let Some(path) = gateway.strip_prefix("unix:") {
+            return Endpoint::Unix(PathBuf::from(path));
+        }
+        let address = gateway.trim_start_matches("http://").trim_end_matches('/');
+        Endpoint::Tcp(address.to_string())
+    }
+
+    /// Resolve the endpoint the way the Node wrapper does: `TALLR_GATEWAY`, `TALLR_SOCKET`,
+    /// the discovery file written by the app, then the default address
+    pub fn discover() -> Self {
+        if let Ok(gateway) = std::env::var("TALLR_GATEWAY") {
+            return Endpoint::parse(&gateway);
+        }
+        if let Ok(socket) = std::env::var("TALLR_SOCKET") {
+            return Endpoint::Unix(PathBuf::from(socket));
+        }
+
+        let discovered = get_discovery_file_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str::<Value>(&contents).ok());
+        if let Some(discovery) = discovered {
+            if let Some(url) = discovery["url"].as_str() {
+                return Endpoint::parse(url);
+            }
+            if let Some(socket) = discovery["socket"].as_str() {
+                return Endpoint::Unix(PathBuf::from(socket));
+            }
+        }
+
+        Endpoint::Tcp(DEFAULT_SERVER_ADDRESS.to_string())
+    }
+
+    fn host(&self) -> &str {
+        match self {
+            Endpoint::Tcp(address) => address,
+            Endpoint::Unix(_) => "localhost",
+        }
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Tcp(address) => write!(f, "http://{address}"),
+            Endpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Read the API token from `TALLR_TOKEN` or the `auth.token` file, without creating one
+pub fn read_auth_token() -> Result<String, String> {
+    if let Ok(token) = std::env::var("TALLR_TOKEN") {
+        return Ok(token);
+    }
+    let token_file = crate::auth::get_auth_token_file_path()?;
+    let token = std::fs::read_to_string(&token_file)
+        .map_err(|e| format!("Failed to read auth token from {}: {e}. Is Tallr running?", token_file.display()))?;
+    Ok(token.trim().to_string())
+}
+
+/// One Server-Sent Event from `/v1/events`
+#[derive(Debug, Clone)]
+pub struct SseEvent {
+    pub id: Option<u64>,
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental reader of an SSE response body
+pub struct EventStream {
+    response: Response,
+    buffer: String,
+    pending: VecDeque<SseEvent>,
+}
+
+impl EventStream {
+    /// Next event, or `None` once the server closes the stream
+    pub async fn next(&mut self) -> Option<Result<SseEvent, String>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+
+            let data = match self.response.chunk().await {
+                Ok(data) => data?,
+                Err(e) => return Some(Err(format!("Event stream error: {e}"))),
+            };
+            self.buffer.push_str(&String::from_utf8_lossy(&data).replace("\r\n", "\n"));
+            self.parse_buffer();
+        }
+    }
+
+    /// Move complete (blank-line terminated) events from the buffer to the queue
+    fn parse_buffer(&mut self) {
+        while let Some(end) = self.buffer.find("\n\n") {
+            let block: String = self.buffer.drain(..end + 2).collect();
+            let mut event = SseEvent { id: None, event: "message".to_string(), data: String::new() };
+            let mut has_data = false;
+
+            for line in block.lines() {
+                let (field, value) = line.split_once(':').unwrap_or((line, ""));
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                match field {
+                    "id" => event.id = value.parse().ok(),
+                    "event" => event.event = value.to_string(),
+                    "data" => {
+                        if has_data {
+                            event.data.push('\n');
+                        }
+                        event.data.push_str(value);
+                        has_data = true;
+                    }
+                    // Comments (keep-alives) and unknown fields are ignored
+                    _ => {}
+                }
+            }
+
+            if has_data {
+                self.pending.push_back(event);
+            }
+        }
+    }
+}
+
+/// Small HTTP client for the `/v1/*` API over TCP or a Unix domain socket
+#[derive(Debug, Clone)]
+pub struct TallrClient {
+    pub endpoint: Endpoint,
+    token: String,
+    http: reqwest::Client,
+}
+
+impl TallrClient {
+    pub fn new(endpoint: Endpoint, token: String) -> Result<Self, String> {
+        let builder = reqwest::Client::builder()
+            .user_agent(concat!("tallrctl/", env!("CARGO_PKG_VERSION")))
+            .no_proxy();
+        let builder = match &endpoint {
+            Endpoint::Tcp(_) => builder,
+            #[cfg(unix)]
+            Endpoint::Unix(path) => builder.unix_socket(path.clone()),
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) => return Err("Unix domain sockets are not supported on this platform".to_string()),
+        };
+        let http = builder.build().map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+        Ok(Self { endpoint, token, http })
+    }
+
+    /// Client for the locally running app, found via environment and discovery file
+    pub fn from_env() -> Result<Self, String> {
+        Self::new(Endpoint::discover(), read_auth_token()?)
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+        extra_headers: &[(&str, String)],
+    ) -> Result<Response, String> {
+        let mut request = self.http
+            .request(method, format!("http://{}{path}", self.endpoint.host()))
+            .bearer_auth(&self.token);
+        if let Some(body) = body {
+            request = request.header("content-type", "application/json").body(body.to_string());
+        }
+        for (name, value) in extra_headers {
+            request = request.header(*name, value);
+        }
+
+        request.send().await.map_err(|e| {
+            if e.is_connect() {
+                format!("Cannot connect to Tallr at {}: {e}. Is Tallr running?", self.endpoint)
+            } else {
+                format!("Request to {path} failed: {e}")
+            }
+        })
+    }
+
+    async fn request(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Bytes, String> {
+        let response = self.send(method, path, body, &[]).await?;
+        let status = response.status();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read response from {path}: {e}"))?;
+
+        if status.is_success() {
+            return Ok(bytes);
+        }
+
+        // Structured errors carry a human readable message
+        let message = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|body| body["message"].as_str().map(str::to_string));
+        Err(match (status, message) {
+            (_, Some(message)) => message,
+            (StatusCode::UNAUTHORIZED, None) => "Unauthorized: the auth token was rejected".to_string(),
+            (StatusCode::FORBIDDEN, None) => format!("Forbidden: the auth token's scope does not allow {path}"),
+            (StatusCode::NOT_FOUND, None) => format!("Not found: {path}"),
+            (status, None) => format!("Request to {path} failed with {status}"),
+        })
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let bytes = self.request(Method::GET, path, None).await?;
+        serde_json::from_slice(&bytes).map_err(|e| format!("Unexpected response from {path}: {e}"))
+    }
+
+    pub async fn get_text(&self, path: &str) -> Result<String, String> {
+        let bytes = self.request(Method::GET, path, None).await?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    pub async fn post(&self, path: &str, body: Value) -> Result<(), String> {
+        self.request(Method::POST, path, Some(&body)).await.map(|_| ())
+    }
+
+    /// POST and decode the JSON response
+    pub async fn post_json<T: DeserializeOwned>(&self, path: &str, body: Value) -> Result<T, String> {
+        let bytes = self.request(Method::POST, path, Some(&body)).await?;
+        serde_json::from_slice(&bytes).map_err(|e| format!("Unexpected response from {path}: {e}"))
+    }
+
+    /// Subscribe to `/v1/events`, resuming after `last_event_id` when given
+    pub async fn events(&self, last_event_id: Option<u64>) -> Result<EventStream, String> {
+        let headers: Vec<(&str, String)> = last_event_id
+            .map(|id| vec![("last-event-id", id.to_string())])
+            .unwrap_or_default();
+        let response = self.send(Method::GET, "/v1/events", None, &headers).await?;
+        if !response.status().is_success() {
+            return Err(format!("Event stream request failed with {}", response.status()));
+        }
+        Ok(EventStream { response, buffer: String::new(), pending: VecDeque::new() })
+    }
+}
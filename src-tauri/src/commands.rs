@@ -1,11 +1,17 @@
-use std::{fs, path::Path};
+use std::{fs, sync::Arc};
 use log::{debug, info, warn, error};
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Manager};
 use tauri_plugin_shell::ShellExt;
 use crate::types::*;
 use crate::state::{APP_STATE, save_app_state};
 use crate::utils::*;
+use crate::desktop::{launch_path, open_with_app_command, OPEN_COMMAND};
+use crate::api::ApiContext;
+use crate::archive::Restore;
 use crate::auth::get_or_create_auth_token;
+use crate::events::{emit_task_event, task_changes};
+use crate::history::{record_transition, task_history, transition_for};
+use crate::transitions::validate_transition;
 
 /// Helper function to get IDE command with proper arguments
 fn get_ide_command_and_args(ide_cmd: &str, project_path: &str) -> (String, Vec<String>) {
@@ -47,7 +53,7 @@ pub async fn open_ide_and_terminal(
             let result = app.shell()
                 .command(&command)
                 .args(&args)
-                .env("PATH", "/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin:/Applications/Visual Studio Code.app/Contents/Resources/app/bin:/Applications/Cursor.app/Contents/Resources/app/bin")
+                .env("PATH", launch_path())
                 .spawn();
                 
             match result {
@@ -58,32 +64,37 @@ pub async fn open_ide_and_terminal(
                 Err(e) => {
                     warn!("IDE command '{command}' failed: {e}. Trying fallback.");
                     
-                    // Try with 'open -a' on macOS
-                    let open_result = app.shell()
-                        .command("open")
-                        .args(["-a", &command, &project_path])
-                        .env("PATH", "/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin")
-                        .spawn();
+                    // Try the platform app launcher ('open -a' on macOS, desktop entry on Linux)
+                    let app_result = match open_with_app_command(&command, &project_path) {
+                        Some((launcher, launcher_args)) => app.shell()
+                            .command(&launcher)
+                            .args(&launcher_args)
+                            .env("PATH", launch_path())
+                            .spawn()
+                            .map(|_| ())
+                            .map_err(|e| e.to_string()),
+                        None => Err(format!("no application entry found for '{command}'")),
+                    };
                         
-                    match open_result {
+                    match app_result {
                         Ok(_) => {
-                            info!("Successfully opened IDE with 'open -a' fallback");
+                            info!("Successfully opened IDE with app launcher fallback");
                             Ok(())
                         }
                         Err(e2) => {
-                            warn!("'open -a' fallback failed: {e2}. Trying directory fallback.");
+                            warn!("App launcher fallback failed: {e2}. Trying directory fallback.");
                             
                             // Last resort: just open the directory
                             app.shell()
-                                .command("open")
+                                .command(OPEN_COMMAND)
                                 .args([&project_path])
-                                .env("PATH", "/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin")
+                                .env("PATH", launch_path())
                                 .spawn()
                                 .map_err(|e3| {
                                     let error_msg = format!(
                                         "All methods failed to open project:\n\
                                         1. IDE command '{command}': {e}\n\
-                                        2. 'open -a' fallback: {e2}\n\
+                                        2. App launcher fallback: {e2}\n\
                                         3. Directory fallback: {e3}"
                                     );
                                     error!("{error_msg}");
@@ -100,9 +111,9 @@ pub async fn open_ide_and_terminal(
             info!("No IDE specified, opening project directory with system default");
             // No IDE specified - just try to open with system default
             app.shell()
-                .command("open")
+                .command(OPEN_COMMAND)
                 .args([&project_path])
-                .env("PATH", "/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin")
+                .env("PATH", launch_path())
                 .spawn()
                 .map_err(|e| {
                     let error_msg = format!("Failed to open project directory: {e}");
@@ -117,50 +128,32 @@ pub async fn open_ide_and_terminal(
 
 #[tauri::command]
 pub async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), String> {
-    let app_data_dir = app.path().app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {e}"))?;
-    
-    // Ensure directory exists
-    fs::create_dir_all(&app_data_dir)
-        .map_err(|e| format!("Failed to create app data dir: {e}"))?;
-    
-    let settings_file = app_data_dir.join("settings.json");
-    let settings_json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {e}"))?;
-    
-    fs::write(&settings_file, settings_json)
-        .map_err(|e| format!("Failed to write settings file: {e}"))?;
-    
-    Ok(())
+    crate::notifications::validate(&settings)?;
+    crate::webhooks::validate(&settings)?;
+    crate::redaction::validate(&settings)?;
+    crate::reports::validate(&settings)?;
+    crate::settings::write_settings(&app, &settings)?;
+    crate::notifications::configure(&settings)?;
+    crate::webhooks::configure(&settings)?;
+    crate::redaction::configure(&settings)?;
+    crate::escalation::configure(&settings);
+    crate::heartbeat::configure(&settings);
+    crate::retention::configure(&settings);
+    crate::reports::configure(&settings)
 }
 
 #[tauri::command]
 pub async fn load_settings(app: AppHandle) -> Result<AppSettings, String> {
-    let app_data_dir = app.path().app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {e}"))?;
-    
-    let settings_file = app_data_dir.join("settings.json");
-    
-    if !settings_file.exists() {
-        return Ok(AppSettings::default());
-    }
-    
-    let settings_content = fs::read_to_string(&settings_file)
-        .map_err(|e| format!("Failed to read settings file: {e}"))?;
-    
-    let settings: AppSettings = serde_json::from_str(&settings_content)
-        .map_err(|e| format!("Failed to parse settings: {e}"))?;
-    
-    Ok(settings)
+    crate::settings::read_settings(&app)
 }
 
 #[tauri::command]
 pub async fn check_cli_permissions() -> Result<bool, String> {
-    let bin_dir = Path::new("/usr/local/bin");
+    let bin_dir = get_cli_install_dir()?;
     
-    // Check if directory exists and is writable
+    // A user-level install directory can simply be created
     if !bin_dir.exists() {
-        return Ok(false);
+        return Ok(cfg!(not(target_os = "macos")));
     }
     
     // Try to check write permissions
@@ -258,12 +251,13 @@ pub async fn install_cli_globally(app: AppHandle) -> Result<(), String> {
         info!("Set executable permissions for CLI at: {cli_source:?}");
     }
     
-    // Ensure /usr/local/bin directory exists
-    let bin_dir = Path::new("/usr/local/bin");
+    // Ensure the install directory exists
+    let bin_dir = get_cli_install_dir()?;
+    let bin_display = bin_dir.display();
     if !bin_dir.exists() {
         // Try to create it
-        if let Err(e) = fs::create_dir_all(bin_dir) {
-            return Err(format!("Cannot create /usr/local/bin: {e}. Please run: sudo mkdir -p /usr/local/bin"));
+        if let Err(e) = fs::create_dir_all(&bin_dir) {
+            return Err(format!("Cannot create {bin_display}: {e}. Please run: sudo mkdir -p {bin_display}"));
         }
     }
     
@@ -274,13 +268,13 @@ pub async fn install_cli_globally(app: AppHandle) -> Result<(), String> {
     }
     let _ = fs::remove_file(&test_file);
     
-    // Create symlink at /usr/local/bin/tallr
+    // Create symlink in the install directory
     let cli_dest = bin_dir.join("tallr");
     
-    // Remove existing symlink if it exists
-    if cli_dest.exists() {
+    // Remove existing symlink if it exists (including a dangling one)
+    if cli_dest.symlink_metadata().is_ok() {
         if let Err(e) = fs::remove_file(&cli_dest) {
-            return Err(format!("Cannot remove existing CLI: {e}. Please run: sudo rm /usr/local/bin/tallr"));
+            return Err(format!("Cannot remove existing CLI: {e}. Please run: sudo rm {}", cli_dest.display()));
         }
     }
     
@@ -288,11 +282,18 @@ pub async fn install_cli_globally(app: AppHandle) -> Result<(), String> {
     #[cfg(unix)]
     {
         if let Err(e) = std::os::unix::fs::symlink(&cli_source, &cli_dest) {
-            return Err(format!("Failed to create symlink: {e}. Please run: sudo ln -s {cli_source:?} /usr/local/bin/tallr"));
+            return Err(format!("Failed to create symlink: {e}. Please run: sudo ln -s {cli_source:?} {}", cli_dest.display()));
         }
     }
     
     info!("Successfully installed CLI at: {cli_dest:?}");
+    
+    // ~/.local/bin is not on every distribution's default PATH
+    let on_path = std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir == bin_dir));
+    if !on_path {
+        warn!("{bin_display} is not on PATH; add it to your shell profile to run `tallr`");
+    }
     Ok(())
 }
 
@@ -319,6 +320,12 @@ pub async fn get_tasks() -> AppState {
     APP_STATE.lock().clone()
 }
 
+/// Full snapshot for clients that detected a gap in event revisions
+#[tauri::command]
+pub async fn get_state_snapshot() -> StateSnapshot {
+    APP_STATE.lock().snapshot()
+}
+
 #[tauri::command]
 pub async fn send_notification(app: AppHandle, title: String, body: String) -> Result<(), String> {
     use tauri_plugin_notification::NotificationExt;
@@ -384,22 +391,35 @@ pub async fn write_frontend_log(level: String, message: String, context: Option<
 pub async fn frontend_update_task_state(
     app_handle: AppHandle,
     task_id: String,
-    state: String,
+    state: TaskState,
     details: Option<String>
 ) -> Result<(), String> {
     let mut app_state = APP_STATE.lock();
     
     if let Some(task) = app_state.tasks.get_mut(&task_id) {
+        validate_transition(&task_id, task.state, state).map_err(|e| e.message)?;
+
+        let before = task.clone();
         task.state = state;
         task.details = details;
         task.updated_at = current_timestamp();
+        let changes = task_changes(&before, task);
+        let transition = (before.state != task.state)
+            .then(|| transition_for(task, Some(before.state), Some("frontend".to_string())));
         app_state.updated_at = current_timestamp();
 
         // Emit event to frontend for real-time updates
-        let _ = app_handle.emit("tasks-updated", &app_state.clone());
+        let revision = app_state.next_revision();
+        if let (Some(transition), Some(task)) = (&transition, app_state.tasks.get(&task_id)) {
+            crate::webhooks::on_transition(transition, task, app_state.projects.get(&task.project_id));
+        }
+        emit_task_event(&app_handle, TaskEvent::TaskUpdated { revision, task_id, changes });
         
         // Save to disk
         drop(app_state); // Release the lock before calling save_app_state
+        if let Some(transition) = transition {
+            record_transition(&transition);
+        }
         if let Err(e) = save_app_state() {
             error!("Failed to save app state: {e}");
             return Err(format!("Failed to save app state: {e}"));
@@ -420,19 +440,32 @@ pub async fn frontend_mark_task_done(
     let mut app_state = APP_STATE.lock();
     
     if let Some(task) = app_state.tasks.get_mut(&task_id) {
-        task.state = "DONE".to_string();
+        validate_transition(&task_id, task.state, TaskState::Done).map_err(|e| e.message)?;
+
+        let before = task.clone();
+        task.state = TaskState::Done;
         task.details = details;
         task.updated_at = current_timestamp();
+        let changes = task_changes(&before, task);
+        let transition = (before.state != task.state)
+            .then(|| transition_for(task, Some(before.state), Some("frontend".to_string())));
         app_state.updated_at = current_timestamp();
 
         // Emit event to frontend for real-time updates
-        let _ = app_handle.emit("tasks-updated", &app_state.clone());
+        let revision = app_state.next_revision();
+        if let (Some(transition), Some(task)) = (&transition, app_state.tasks.get(&task_id)) {
+            crate::webhooks::on_transition(transition, task, app_state.projects.get(&task.project_id));
+        }
+        emit_task_event(&app_handle, TaskEvent::TaskUpdated { revision, task_id, changes });
         
         // Update tray menu
         drop(app_state); // Release the lock before calling update_tray_menu
         crate::tray::update_tray_menu(&app_handle);
 
         // Save to disk
+        if let Some(transition) = transition {
+            record_transition(&transition);
+        }
         if let Err(e) = save_app_state() {
             error!("Failed to save app state: {e}");
             return Err(format!("Failed to save app state: {e}"));
@@ -449,30 +482,39 @@ pub async fn frontend_delete_task(
     app_handle: AppHandle,
     task_id: String
 ) -> Result<(), String> {
-    let mut app_state = APP_STATE.lock();
-    
-    if app_state.tasks.remove(&task_id).is_some() {
-        app_state.updated_at = current_timestamp();
+    frontend_archive_task(app_handle, task_id).await
+}
 
-        // Emit event to frontend for real-time updates
-        let _ = app_handle.emit("tasks-updated", &app_state.clone());
-        
-        // Update tray menu
-        drop(app_state); // Release the lock before calling update_tray_menu
-        crate::tray::update_tray_menu(&app_handle);
+#[tauri::command]
+pub async fn frontend_archive_task(
+    app_handle: AppHandle,
+    task_id: String
+) -> Result<(), String> {
+    let ctx = ApiContext::new(APP_STATE.clone(), Arc::new(app_handle));
+    match crate::archive::archive_task(&ctx, &task_id, current_timestamp())? {
+        Some(_) => Ok(()),
+        None => Err("Task not found".to_string()),
+    }
+}
 
-        // Save to disk
-        if let Err(e) = save_app_state() {
-            error!("Failed to save app state: {e}");
-            return Err(format!("Failed to save app state: {e}"));
-        }
-        
-        Ok(())
-    } else {
-        Err("Task not found".to_string())
+#[tauri::command]
+pub async fn frontend_restore_task(
+    app_handle: AppHandle,
+    task_id: String
+) -> Result<Task, String> {
+    let ctx = ApiContext::new(APP_STATE.clone(), Arc::new(app_handle));
+    match crate::archive::restore_task(&ctx, &task_id, current_timestamp())? {
+        Restore::Restored(task) => Ok(task),
+        Restore::NotArchived => Err("Task not found in archive".to_string()),
+        Restore::AlreadyLive => Err("A task with this id is already on the dashboard".to_string()),
     }
 }
 
+#[tauri::command]
+pub async fn search_archive_cmd(query: Option<String>, limit: Option<u32>) -> Result<Vec<ArchivedTask>, String> {
+    crate::archive::search(query.as_deref(), limit)
+}
+
 #[tauri::command]
 pub async fn frontend_toggle_task_pin(
     app_handle: AppHandle,
@@ -482,12 +524,15 @@ pub async fn frontend_toggle_task_pin(
     let mut app_state = APP_STATE.lock();
     
     if let Some(task) = app_state.tasks.get_mut(&task_id) {
+        let before = task.clone();
         task.pinned = pinned;
         task.updated_at = current_timestamp();
+        let changes = task_changes(&before, task);
         app_state.updated_at = current_timestamp();
 
         // Emit event to frontend for real-time updates
-        let _ = app_handle.emit("tasks-updated", &app_state.clone());
+        let revision = app_state.next_revision();
+        emit_task_event(&app_handle, TaskEvent::TaskUpdated { revision, task_id, changes });
         
         // Save to disk
         drop(app_state); // Release the lock before calling save_app_state
@@ -502,6 +547,66 @@ pub async fn frontend_toggle_task_pin(
     }
 }
 
+#[tauri::command]
+pub async fn frontend_snooze_task(task_id: String, minutes: u64) -> Result<(), String> {
+    if !APP_STATE.lock().tasks.contains_key(&task_id) {
+        return Err("Task not found".to_string());
+    }
+    crate::notifications::snooze_task(&task_id, minutes);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_auth_audit_cmd(limit: Option<u32>) -> Result<AuthAudit, String> {
+    crate::audit::report(limit)
+}
+
+#[tauri::command]
+pub async fn list_api_tokens_cmd() -> Result<Vec<ApiToken>, String> {
+    crate::tokens::list()
+}
+
+#[tauri::command]
+pub async fn create_api_token_cmd(name: String, scope: TokenScope) -> Result<IssuedToken, String> {
+    crate::tokens::create(&name, scope, current_timestamp())?
+        .ok_or_else(|| format!("A token named '{name}' already exists"))
+}
+
+#[tauri::command]
+pub async fn rotate_api_token_cmd(name: String) -> Result<IssuedToken, String> {
+    crate::tokens::rotate(&name, current_timestamp())?
+        .ok_or_else(|| format!("No token named '{name}'"))
+}
+
+#[tauri::command]
+pub async fn revoke_api_token_cmd(name: String) -> Result<(), String> {
+    if crate::tokens::revoke(&name)? {
+        Ok(())
+    } else {
+        Err(format!("No token named '{name}'"))
+    }
+}
+
+#[tauri::command]
+pub async fn get_task_history_cmd(task_id: String) -> Result<Vec<StateTransition>, String> {
+    task_history(&task_id)
+}
+
+#[tauri::command]
+pub async fn get_stats_cmd(since: Option<i64>, until: Option<i64>) -> Result<StatsReport, String> {
+    crate::analytics::collect_stats(&APP_STATE, since, until)
+}
+
+#[tauri::command]
+pub async fn get_report_cmd(period: String, until: Option<i64>) -> Result<DigestReport, String> {
+    crate::reports::build_report(&APP_STATE, period.parse()?, until)
+}
+
+#[tauri::command]
+pub async fn get_report_markdown_cmd(period: String, until: Option<i64>) -> Result<String, String> {
+    crate::reports::build_report(&APP_STATE, period.parse()?, until).map(|report| crate::reports::render_markdown(&report))
+}
+
 #[tauri::command]
 pub async fn frontend_get_debug_data(task_id: Option<String>) -> Result<serde_json::Value, String> {
     let app_state = APP_STATE.lock();
@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::{fs, path::Path};
+
+/// Command that opens a file or directory with the desktop's default handler
+#[cfg(target_os = "macos")]
+pub const OPEN_COMMAND: &str = "open";
+#[cfg(all(unix, not(target_os = "macos")))]
+pub const OPEN_COMMAND: &str = "xdg-open";
+#[cfg(windows)]
+pub const OPEN_COMMAND: &str = "explorer";
+
+/// PATH for spawning IDE launchers. GUI apps start with a minimal PATH,
+/// so add the places editors usually install their command line shims.
+pub fn launch_path() -> String {
+    #[cfg(target_os = "macos")]
+    let extra = vec![
+        PathBuf::from("/usr/local/bin"),
+        PathBuf::from("/opt/homebrew/bin"),
+        PathBuf::from("/usr/bin"),
+        PathBuf::from("/bin"),
+        PathBuf::from("/Applications/Visual Studio Code.app/Contents/Resources/app/bin"),
+        PathBuf::from("/Applications/Cursor.app/Contents/Resources/app/bin"),
+    ];
+
+    #[cfg(not(target_os = "macos"))]
+    let home = std::env::var("HOME").map(PathBuf::from).unwrap_or_default();
+    #[cfg(not(target_os = "macos"))]
+    let extra = vec![
+        home.join(".local/bin"),
+        PathBuf::from("/usr/local/bin"),
+        PathBuf::from("/usr/bin"),
+        PathBuf::from("/bin"),
+        PathBuf::from("/snap/bin"),
+        home.join(".local/share/flatpak/exports/bin"),
+        PathBuf::from("/var/lib/flatpak/exports/bin"),
+        home.join(".local/share/JetBrains/Toolbox/scripts"),
+    ];
+
+    let inherited = std::env::var_os("PATH").unwrap_or_default();
+    let paths: Vec<PathBuf> = std::env::split_paths(&inherited).chain(extra).collect();
+    std::env::join_paths(paths)
+        .map(|joined| joined.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Command that opens `path` with a named application, if the platform has one:
+/// `open -a` on macOS, the app's desktop entry via `gtk-launch` on Linux
+pub fn open_with_app_command(app_name: &str, path: &str) -> Option<(String, Vec<String>)> {
+    #[cfg(target_os = "macos")]
+    return Some(("open".to_string(), vec!["-a".to_string(), app_name.to_string(), path.to_string()]));
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return find_desktop_entry(app_name)
+        .map(|desktop_id| ("gtk-launch".to_string(), vec![desktop_id, path.to_string()]));
+
+    #[cfg(windows)]
+    {
+        let _ = (app_name, path);
+        None
+    }
+}
+
+/// Directories holding `.desktop` files, most specific first
+#[cfg(all(unix, not(target_os = "macos")))]
+fn application_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").map(PathBuf::from).unwrap_or_default();
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home.join(".local/share"));
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    std::iter::once(data_home)
+        .chain(std::env::split_paths(&data_dirs))
+        .chain([
+            home.join(".local/share/flatpak/exports/share"),
+            PathBuf::from("/var/lib/flatpak/exports/share"),
+            PathBuf::from("/var/lib/snapd/desktop"),
+        ])
+        .map(|dir| dir.join("applications"))
+        .collect()
+}
+
+/// Program named by a desktop entry's `Exec=` line
+#[cfg(all(unix, not(target_os = "macos")))]
+fn desktop_entry_program(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let exec = contents.lines().find_map(|line| line.strip_prefix("Exec="))?;
+    let program = exec.split_whitespace().next()?.trim_matches('"');
+    Path::new(program).file_name().map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Find the desktop entry id (e.g. `code.desktop`) for an app command or name.
+/// Matches the file name (`code`, `com.visualstudio.code`) or the program in `Exec=`.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn find_desktop_entry(app: &str) -> Option<String> {
+    let wanted = app.to_lowercase();
+    let mut exec_match = None;
+
+    for dir in application_dirs() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("desktop") {
+                continue;
+            }
+            let Some(stem) = path.file_stem().map(|stem| stem.to_string_lossy().to_lowercase()) else {
+                continue;
+            };
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+
+            if stem == wanted || stem.ends_with(&format!(".{wanted}")) {
+                return Some(file_name);
+            }
+            if exec_match.is_none() && desktop_entry_program(&path).is_some_and(|p| p.to_lowercase() == wanted) {
+                exec_match = Some(file_name);
+            }
+        }
+    }
+
+    exec_match
+}
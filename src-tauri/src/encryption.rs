@@ -0,0 +1,256 @@
+use std::{fs, path::{Path, PathBuf}, sync::atomic::{AtomicBool, Ordering}};
+use chacha20poly1305::{aead::{Aead, KeyInit}, ChaCha20Poly1305, Key, Nonce};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use rusqlite::{functions::FunctionFlags, Connection, OptionalExtension};
+use crate::keychain;
+use crate::utils::{get_app_data_dir, get_sessions_file_path, restrict_permissions};
+
+/// Marks an encrypted value: `enc:v1:` followed by the hex nonce and ciphertext
+const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+/// Plaintext stored sealed in the database to tell a wrong key from a corrupt value
+const KEY_CHECK: &str = "tallr";
+
+/// Columns holding session content, sealed when encryption is on
+const SEALED_COLUMNS: [(&str, &[&str]); 5] = [
+    ("projects", &["name", "repo_path", "github_url"]),
+    ("tasks", &["title", "details"]),
+    ("debug_data", &["data"]),
+    ("archived_tasks", &["title", "details"]),
+    ("state_transitions", &["details"]),
+];
+
+// Cipher for the open database's state key, if there is one
+static CIPHER: Lazy<RwLock<Option<ChaCha20Poly1305>>> = Lazy::new(|| RwLock::new(None));
+// Whether new values are encrypted; off while a database is being decrypted
+static SEALING: AtomicBool = AtomicBool::new(false);
+
+/// Get path to the state key file (next to `auth.token`), used only with `TALLR_STATE_KEY_FILE=1`
+pub fn get_state_key_file_path() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("state.key"))
+}
+
+fn parse_key(hex_key: &str) -> Result<[u8; 32], String> {
+    hex::decode(hex_key.trim()).ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| "State key must be 64 hex characters".to_string())
+}
+
+/// Write a new random key readable only by the current user
+fn create_key_file(path: &Path) -> Result<[u8; 32], String> {
+    let key: [u8; 32] = rand::random();
+    fs::write(path, hex::encode(key))
+        .map_err(|e| format!("Failed to write state key file: {e}"))?;
+    restrict_permissions(path)?;
+    info!("Generated state encryption key at {}", path.display());
+    Ok(key)
+}
+
+/// Whether `TALLR_ENCRYPT_STATE` asks for encryption to be turned on (`1`) or off (`0`)
+fn requested() -> Option<bool> {
+    match std::env::var("TALLR_ENCRYPT_STATE").ok()?.trim() {
+        "1" | "true" | "on" => Some(true),
+        "0" | "false" | "off" => Some(false),
+        other => {
+            warn!("Ignoring TALLR_ENCRYPT_STATE={other}; use 1 or 0");
+            None
+        }
+    }
+}
+
+/// Whether `TALLR_STATE_KEY_FILE=1` keeps the state key in `state.key` instead of the OS secret store
+fn key_file_requested() -> bool {
+    std::env::var("TALLR_STATE_KEY_FILE").is_ok_and(|value| matches!(value.trim(), "1" | "true" | "on"))
+}
+
+fn read_key_file(path: &Path) -> Result<[u8; 32], String> {
+    let key = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read state key file: {e}"))?;
+    parse_key(&key)
+}
+
+/// The state key from `TALLR_STATE_KEY`, the OS secret store or, with `TALLR_STATE_KEY_FILE=1`, `state.key`.
+/// A key is generated when encryption is requested and there is no key yet.
+fn load_key() -> Result<Option<[u8; 32]>, String> {
+    if let Ok(key) = std::env::var("TALLR_STATE_KEY") {
+        return parse_key(&key).map(Some);
+    }
+    let path = get_state_key_file_path()?;
+    if key_file_requested() {
+        if path.exists() {
+            return read_key_file(&path).map(Some);
+        }
+        if requested() == Some(true) {
+            return create_key_file(&path).map(Some);
+        }
+        return Ok(None);
+    }
+
+    if let Some(key) = keychain::load() {
+        return parse_key(&key).map(Some);
+    }
+    // A key file from before the OS secret store was used is moved there
+    if path.exists() {
+        let key = read_key_file(&path)?;
+        match keychain::store(&hex::encode(key)) {
+            Ok(()) => {
+                fs::remove_file(&path).map_err(|e| format!("Failed to remove state key file: {e}"))?;
+                info!("Moved the state key from {} to the OS secret store", path.display());
+            }
+            Err(e) => warn!("Keeping the state key in {}: {e}", path.display()),
+        }
+        return Ok(Some(key));
+    }
+    if requested() == Some(true) {
+        let key: [u8; 32] = rand::random();
+        keychain::store(&hex::encode(key)).map_err(|e| {
+            format!("{e}; set TALLR_STATE_KEY, or TALLR_STATE_KEY_FILE=1 to keep the key in {}", path.display())
+        })?;
+        info!("Generated state encryption key in the OS secret store");
+        return Ok(Some(key));
+    }
+    Ok(None)
+}
+
+/// Encrypt a value for storage; values pass through unchanged when encryption is off
+pub fn seal(value: &str) -> Result<String, String> {
+    let cipher = CIPHER.read();
+    let Some(cipher) = cipher.as_ref().filter(|_| SEALING.load(Ordering::Relaxed)) else {
+        return Ok(value.to_string());
+    };
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), value.as_bytes())
+        .map_err(|_| "Failed to encrypt value".to_string())?;
+    Ok(format!("{PREFIX}{}{}", hex::encode(nonce), hex::encode(ciphertext)))
+}
+
+/// Decrypt a stored value. Plaintext values written before encryption was turned on are returned as is.
+pub fn reveal(value: &str) -> Result<String, String> {
+    let Some(sealed) = value.strip_prefix(PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let cipher = CIPHER.read();
+    let cipher = cipher.as_ref().ok_or("Value is encrypted but no state key is loaded")?;
+    let bytes = hex::decode(sealed).map_err(|_| "Encrypted value is corrupt".to_string())?;
+    if bytes.len() < NONCE_LEN {
+        return Err("Encrypted value is corrupt".to_string());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt value: wrong state key or corrupt data".to_string())?;
+    String::from_utf8(plaintext).map_err(|_| "Decrypted value is not UTF-8".to_string())
+}
+
+/// Make `seal(x)` and `reveal(x)` available to SQL; both map NULL to NULL
+fn register_functions(conn: &Connection) -> Result<(), String> {
+    let register = |name: &str, flags: FunctionFlags, f: fn(&str) -> Result<String, String>| {
+        conn.create_scalar_function(name, 1, flags, move |ctx| {
+            ctx.get::<Option<String>>(0)?
+                .map(|value| f(&value))
+                .transpose()
+                .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))
+        })
+    };
+    register("seal", FunctionFlags::SQLITE_UTF8, seal)
+        .and_then(|_| register("reveal", FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC, reveal))
+        .map_err(|e| format!("Failed to register encryption functions: {e}"))
+}
+
+/// Re-write every sealed column with the current cipher: encrypts plaintext when encryption
+/// was just turned on, and decrypts everything when it was turned off
+fn reseal(conn: &mut Connection, encrypted: bool) -> Result<(), String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to re-encrypt database: {e}");
+    let tx = conn.transaction().map_err(db_err)?;
+    for (table, columns) in SEALED_COLUMNS {
+        let assignments: Vec<String> = columns.iter()
+            .map(|column| format!("{column} = seal(reveal({column}))"))
+            .collect();
+        tx.execute(&format!("UPDATE {table} SET {}", assignments.join(", ")), []).map_err(db_err)?;
+    }
+    tx.execute("DELETE FROM encryption", []).map_err(db_err)?;
+    if encrypted {
+        tx.execute("INSERT INTO encryption (id, key_check) VALUES (1, seal(?1))", [KEY_CHECK]).map_err(db_err)?;
+    }
+    tx.commit().map_err(db_err)?;
+
+    // Rewrite the file so no page still holds the old values
+    conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);").map_err(db_err)
+}
+
+/// Load the state key and bring the open database in line with it. Encrypting an existing
+/// plaintext database (or decrypting it with `TALLR_ENCRYPT_STATE=0`) happens here, once.
+pub fn prepare(conn: &mut Connection) -> Result<(), String> {
+    register_functions(conn)?;
+    let key = load_key()?;
+    *CIPHER.write() = key.map(|key| ChaCha20Poly1305::new(Key::from_slice(&key)));
+    SEALING.store(key.is_some(), Ordering::Relaxed);
+
+    let key_check: Option<String> = conn
+        .query_row("SELECT key_check FROM encryption WHERE id = 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to read encryption status: {e}"))?;
+
+    let turn_off = requested() == Some(false);
+    match (key_check, key.is_some()) {
+        (Some(_), false) => {
+            Err("The database is encrypted but no state key was found; set TALLR_STATE_KEY or restore the key".to_string())
+        }
+        (Some(check), true) => {
+            if reveal(&check).ok().as_deref() != Some(KEY_CHECK) {
+                return Err("The state key does not match the encrypted database".to_string());
+            }
+            if turn_off {
+                SEALING.store(false, Ordering::Relaxed);
+                reseal(conn, false)?;
+                remove_key()?;
+                info!("Decrypted the database; state is stored in plaintext again");
+            }
+            Ok(())
+        }
+        (None, true) if turn_off => {
+            SEALING.store(false, Ordering::Relaxed);
+            remove_key()
+        }
+        (None, true) => {
+            reseal(conn, true)?;
+            info!("Encrypted the database with the state key");
+            Ok(())
+        }
+        (None, false) => Ok(()),
+    }
+}
+
+/// Forget the generated key once nothing is encrypted with it; a key from the environment is left alone
+fn remove_key() -> Result<(), String> {
+    if std::env::var_os("TALLR_STATE_KEY").is_some() {
+        return Ok(());
+    }
+    if !key_file_requested() {
+        keychain::delete();
+    }
+    let path = get_state_key_file_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(path).map_err(|e| format!("Failed to remove state key file: {e}"))
+}
+
+/// Whether values are being encrypted
+pub fn is_enabled() -> bool {
+    SEALING.load(Ordering::Relaxed)
+}
+
+/// Remove the plaintext copy of the legacy sessions file once its contents are encrypted in the database
+pub fn remove_plaintext_sessions_file() {
+    let Ok(sessions_file) = get_sessions_file_path() else { return };
+    for path in [sessions_file.with_extension("json.migrated"), sessions_file.with_extension("json.backup")] {
+        if path.exists() {
+            match fs::remove_file(&path) {
+                Ok(()) => info!("Removed plaintext {}", path.display()),
+                Err(e) => warn!("Failed to remove plaintext {}: {e}", path.display()),
+            }
+        }
+    }
+}
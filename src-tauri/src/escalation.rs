@@ -0,0 +1,189 @@
+use std::{collections::HashMap, time::Duration};
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use crate::api::ApiContext;
+use crate::notifications::{is_snoozed, is_suppressed, notify_escalation};
+use crate::types::{AppSettings, EscalationSettings, Project, Task, TaskState};
+use crate::utils::current_timestamp;
+
+/// How often waiting tasks are re-checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+static SETTINGS: Lazy<RwLock<EscalationSettings>> = Lazy::new(Default::default);
+
+/// Reminders sent for a task since it entered its current state at `since` (its `updated_at`).
+/// Only this bookkeeping lives in memory; after a restart the persisted `updated_at` still
+/// tells how long a task has waited, so it is escalated again on the first check.
+struct Escalation {
+    since: i64,
+    reminders: u32,
+    last_sent: i64,
+}
+
+static ESCALATED: Lazy<Mutex<HashMap<String, Escalation>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Load the escalation settings
+pub fn configure(settings: &AppSettings) {
+    let escalation = settings.escalation.clone();
+    info!("Escalation {}: PENDING after {}s, ERROR after {}s, repeat every {}s",
+          if escalation.enabled { "enabled" } else { "disabled" },
+          escalation.pending_after_seconds, escalation.error_after_seconds, escalation.repeat_every_seconds);
+    *SETTINGS.write() = escalation;
+}
+
+/// Whether the tray icon should blink: some task is escalated and blinking is on
+pub fn blinking() -> bool {
+    SETTINGS.read().blink_tray && !ESCALATED.lock().is_empty()
+}
+
+/// Seconds the task has waited, if that is past its state's threshold (0 turns a state off)
+fn overdue(settings: &EscalationSettings, task: &Task, now: i64) -> Option<i64> {
+    let threshold = match task.state {
+        TaskState::Pending => settings.pending_after_seconds,
+        TaskState::Error => settings.error_after_seconds,
+        _ => return None,
+    };
+    let waited = now - task.updated_at;
+    (settings.enabled && threshold > 0 && waited >= threshold as i64).then_some(waited)
+}
+
+/// Seconds the task has waited, if a reminder may go out: it is overdue, not snoozed,
+/// and its notification rules do not suppress it
+fn escalating(settings: &EscalationSettings, task: &Task, project: Option<&Project>, now: i64) -> Option<i64> {
+    let waited = overdue(settings, task, now)?;
+    (!is_snoozed(&task.id, now) && !is_suppressed(task, project, waited)).then_some(waited)
+}
+
+/// Re-check every task and send the reminders that are due at `now`
+pub fn check(ctx: &ApiContext, now: i64) {
+    let settings = SETTINGS.read().clone();
+    let state = ctx.state.lock();
+    let mut escalated = ESCALATED.lock();
+    let was_active = !escalated.is_empty();
+
+    // A task that moved on (or was snoozed, suppressed or deleted) starts over
+    escalated.retain(|task_id, escalation| {
+        state.tasks.get(task_id).is_some_and(|task| {
+            let project = state.projects.get(&task.project_id);
+            task.updated_at == escalation.since && escalating(&settings, task, project, now).is_some()
+        })
+    });
+
+    for task in state.tasks.values() {
+        let project = state.projects.get(&task.project_id);
+        let Some(waited) = escalating(&settings, task, project, now) else { continue };
+        let due = escalated.get(&task.id).is_none_or(|escalation| {
+            settings.repeat_every_seconds > 0 && now - escalation.last_sent >= settings.repeat_every_seconds as i64
+        });
+        if !due {
+            continue;
+        }
+
+        let escalation = escalated.entry(task.id.clone())
+            .or_insert(Escalation { since: task.updated_at, reminders: 0, last_sent: now });
+        escalation.reminders += 1;
+        escalation.last_sent = now;
+        info!("Escalating task {}: {} for {waited}s (reminder {})", task.id, task.state, escalation.reminders);
+
+        notify_escalation(ctx.events.as_ref(), task, project, waited);
+        if settings.webhooks {
+            crate::webhooks::on_escalation(task, project, waited, escalation.reminders);
+        }
+    }
+
+    let active = !escalated.is_empty();
+    drop(escalated);
+    drop(state);
+    if active != was_active {
+        debug!("Escalation {}", if active { "started" } else { "cleared" });
+        ctx.events.state_changed();
+    }
+}
+
+/// Background scheduler; runs for the life of the HTTP server
+pub async fn run(ctx: ApiContext) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        check(&ctx, current_timestamp());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A task that entered `state` at time 1000
+    fn task(state: TaskState) -> Task {
+        Task {
+            id: "task".to_string(),
+            project_id: "project".to_string(),
+            agent: "claude".to_string(),
+            title: "Refactor billing".to_string(),
+            state,
+            details: None,
+            created_at: 0,
+            updated_at: 1000,
+            pinned: false,
+            detection_method: None,
+            last_heartbeat: None,
+        }
+    }
+
+    #[test]
+    fn waiting_tasks_are_overdue_from_their_threshold() {
+        let settings = EscalationSettings { pending_after_seconds: 60, error_after_seconds: 120, ..Default::default() };
+        assert_eq!(overdue(&settings, &task(TaskState::Pending), 1059), None);
+        assert_eq!(overdue(&settings, &task(TaskState::Pending), 1060), Some(60));
+        assert_eq!(overdue(&settings, &task(TaskState::Error), 1060), None);
+        assert_eq!(overdue(&settings, &task(TaskState::Error), 1500), Some(500));
+    }
+
+    #[test]
+    fn only_pending_and_error_tasks_escalate() {
+        let settings = EscalationSettings::default();
+        for state in [TaskState::Idle, TaskState::Working, TaskState::Done, TaskState::Cancelled, TaskState::Stale] {
+            assert_eq!(overdue(&settings, &task(state), 1_000_000), None, "{state}");
+        }
+    }
+
+    #[test]
+    fn a_threshold_of_zero_turns_a_state_off() {
+        let settings = EscalationSettings { pending_after_seconds: 0, error_after_seconds: 60, ..Default::default() };
+        assert_eq!(overdue(&settings, &task(TaskState::Pending), 1_000_000), None);
+        assert_eq!(overdue(&settings, &task(TaskState::Error), 1060), Some(60));
+    }
+
+    #[test]
+    fn tasks_in_suppressed_projects_are_never_escalated() {
+        let settings = AppSettings {
+            notification_rules: serde_json::from_value(serde_json::json!([
+                { "name": "noisy scratch repo", "project": "scratch-*", "action": "suppress" }
+            ])).unwrap(),
+            ..Default::default()
+        };
+        crate::notifications::configure(&settings).unwrap();
+        let project = |name: &str| Project {
+            id: "project".to_string(),
+            name: name.to_string(),
+            repo_path: format!("/src/{name}"),
+            preferred_ide: "cursor".to_string(),
+            github_url: None,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let escalation = EscalationSettings::default();
+        let pending = task(TaskState::Pending);
+        for now in [1300, 1900, 100_000] {
+            assert_eq!(escalating(&escalation, &pending, Some(&project("scratch-ui")), now), None);
+        }
+        assert_eq!(escalating(&escalation, &pending, Some(&project("billing")), 1300), Some(300));
+    }
+
+    #[test]
+    fn nothing_is_overdue_when_escalation_is_disabled() {
+        let settings = EscalationSettings { enabled: false, ..Default::default() };
+        assert_eq!(overdue(&settings, &task(TaskState::Pending), 1_000_000), None);
+    }
+}
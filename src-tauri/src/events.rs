@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+use crate::types::{Task, TaskEvent};
+
+/// Number of past events kept for `Last-Event-ID` resume
+const EVENT_HISTORY_LIMIT: usize = 256;
+
+/// Receiver of UI-facing events. The Tauri app handle is the production sink;
+/// tests and other front ends can supply their own.
+pub trait EventSink: Send + Sync + 'static {
+    /// Deliver a named event with its JSON payload
+    fn emit_event(&self, event: &str, payload: &Value);
+
+    /// Called after a change that affects the aggregate state (e.g. to refresh the tray)
+    fn state_changed(&self) {}
+
+    /// Publish an event to HTTP stream subscribers, by default on the process-wide event bus
+    fn publish(&self, event: &TaskEvent) {
+        EVENT_BUS.publish(event);
+    }
+
+    /// Subscribe to the events `publish` delivers, returning retained events after `last_event_id` to replay first
+    fn subscribe(&self, last_event_id: Option<u64>) -> (Vec<StreamEvent>, broadcast::Receiver<StreamEvent>) {
+        EVENT_BUS.subscribe(last_event_id)
+    }
+}
+
+impl EventSink for AppHandle {
+    fn emit_event(&self, event: &str, payload: &Value) {
+        let _ = Emitter::emit(self, event, payload);
+    }
+
+    fn state_changed(&self) {
+        crate::tray::update_tray_menu(self);
+    }
+}
+
+/// A change published to HTTP event stream subscribers
+#[derive(Debug, Clone)]
+pub struct StreamEvent {
+    pub id: u64,
+    pub event: String,
+    pub data: String,
+}
+
+/// Live event channel plus the recent history `Last-Event-ID` resumes from
+pub struct EventBus {
+    inner: Mutex<EventHistory>,
+}
+
+struct EventHistory {
+    sender: broadcast::Sender<StreamEvent>,
+    events: VecDeque<StreamEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_HISTORY_LIMIT);
+        Self {
+            inner: Mutex::new(EventHistory {
+                sender,
+                events: VecDeque::with_capacity(EVENT_HISTORY_LIMIT),
+            }),
+        }
+    }
+}
+
+impl EventBus {
+    /// Publish an event to stream subscribers and keep it for resume.
+    /// The event id is the state revision, so `Last-Event-ID` doubles as a revision cursor.
+    pub fn publish(&self, event: &TaskEvent) {
+        let data = match serde_json::to_string(event) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("Failed to serialize {} event: {e}", event.name());
+                return;
+            }
+        };
+
+        let mut history = self.inner.lock();
+        let stream_event = StreamEvent {
+            id: event.revision(),
+            event: event.name().to_string(),
+            data,
+        };
+
+        if history.events.len() == EVENT_HISTORY_LIMIT {
+            history.events.pop_front();
+        }
+        history.events.push_back(stream_event.clone());
+
+        // No receivers is not an error, it just means nobody is watching
+        let _ = history.sender.send(stream_event);
+    }
+
+    /// Subscribe to new events, returning any retained events after `last_event_id` to replay first
+    pub fn subscribe(&self, last_event_id: Option<u64>) -> (Vec<StreamEvent>, broadcast::Receiver<StreamEvent>) {
+        // Hold the lock while subscribing so no event falls between replay and live stream
+        let history = self.inner.lock();
+        let replay = match last_event_id {
+            Some(last_id) => history.events.iter().filter(|e| e.id > last_id).cloned().collect(),
+            None => Vec::new(),
+        };
+        (replay, history.sender.subscribe())
+    }
+}
+
+// Process-wide event bus shared by the Tauri emitters and the SSE endpoint
+static EVENT_BUS: Lazy<EventBus> = Lazy::new(EventBus::default);
+
+/// Emit a change event to the sink and publish it on the event stream.
+/// Callers hold the app state lock so events go out in revision order.
+pub fn emit_task_event(sink: &dyn EventSink, event: TaskEvent) {
+    match serde_json::to_value(&event) {
+        Ok(payload) => sink.emit_event(event.name(), &payload),
+        Err(e) => log::error!("Failed to serialize {} event: {e}", event.name()),
+    }
+    sink.publish(&event);
+}
+
+/// Fields that differ between two versions of a task, keyed by their camelCase names
+pub fn task_changes(before: &Task, after: &Task) -> Map<String, Value> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Map::new();
+    };
+
+    after
+        .into_iter()
+        .filter(|(field, value)| before.get(field) != Some(value))
+        .collect()
+}
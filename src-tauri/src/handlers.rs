@@ -1,44 +1,139 @@
+use std::convert::Infallible;
 use axum::{
     extract::State as AxumState,
     http::{StatusCode, HeaderMap},
-    response::Json,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
 };
 use log::{debug, info, warn, error};
-use tauri::{AppHandle, Emitter};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
 use crate::types::*;
-use crate::auth::validate_auth_header;
-use crate::state::{APP_STATE, save_app_state};
+use crate::auth::{authorize, authorize_metrics, Access};
+use crate::events::{emit_task_event, task_changes, StreamEvent};
+use crate::heartbeat::{self, Heartbeat};
+use crate::history::{record_transition, task_history, transition_for};
+use crate::notifications::{on_task_update, snooze_task};
+use crate::redaction::{redact_debug_data, redact_details};
+use crate::api::ApiContext;
+use crate::archive::{self, Restore};
+use crate::audit;
+use crate::state::persist_state;
+use crate::tokens;
+use crate::transitions::{validate_transition, TransitionError};
 use crate::utils::current_timestamp;
+use crate::webhooks::{on_transition, recent_deliveries};
 
-/// GET /v1/state - Return current application state
-pub async fn get_state(headers: HeaderMap) -> Result<Json<AppState>, StatusCode> {
+/// Error returned by handlers that can fail with a structured body
+#[derive(Debug)]
+pub enum ApiError {
+    Status(StatusCode),
+    InvalidTransition(TransitionError),
+}
+
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        ApiError::Status(status)
+    }
+}
+
+impl From<TransitionError> for ApiError {
+    fn from(err: TransitionError) -> Self {
+        ApiError::InvalidTransition(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::Status(status) => status.into_response(),
+            ApiError::InvalidTransition(err) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(err)).into_response()
+            }
+        }
+    }
+}
+
+/// GET /v1/state - Return current application state; debug buffers are left out unless the token is admin
+pub async fn get_state(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+) -> Result<Json<AppState>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Read) {
         warn!("Unauthorized access attempt to /v1/state");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
     debug!("Returning app state");
-    let state = APP_STATE.lock().clone();
+    let mut state = ctx.state.lock().clone();
+    if authorize(&headers, Access::Admin).is_err() {
+        state.debug_data.clear();
+    }
     Ok(Json(state))
 }
 
+/// GET /v1/events - Server-Sent Events stream of task changes
+pub async fn stream_events(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Read) {
+        warn!("Unauthorized access attempt to /v1/events");
+        return Err(status);
+    }
+
+    // Resume after the last event the client saw, if it tells us
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    debug!("Event stream subscriber connected (last event id: {last_event_id:?})");
+    let (replay, receiver) = ctx.events.subscribe(last_event_id);
+
+    // Lagging subscribers skip the events they missed rather than closing the stream
+    let live = BroadcastStream::new(receiver).filter_map(|result| result.ok());
+    let stream = tokio_stream::iter(replay)
+        .chain(live)
+        .map(|stream_event: StreamEvent| {
+            Ok(Event::default()
+                .id(stream_event.id.to_string())
+                .event(stream_event.event)
+                .data(stream_event.data))
+        });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 /// POST /v1/tasks/upsert - Create or update task and project
 pub async fn upsert_task(
     headers: HeaderMap,
-    AxumState(app_handle): AxumState<AppHandle>,
-    Json(req): Json<UpsertRequest>,
-) -> Result<Json<()>, StatusCode> {
+    AxumState(ctx): AxumState<ApiContext>,
+    Json(mut req): Json<UpsertRequest>,
+) -> Result<Json<()>, ApiError> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Report) {
         warn!("Unauthorized access attempt to /v1/tasks/upsert");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status.into());
     }
+    redact_details(&req.task.id, &mut req.task.details);
     
     info!("Upserting task: {} for project: {}", req.task.id, req.project.name);
-    let mut state = APP_STATE.lock();
+    let mut state = ctx.state.lock();
     let now = current_timestamp();
+
+    // Reject illegal moves before touching the project or task
+    if let Some(existing) = state.tasks.get(&req.task.id) {
+        if let Err(e) = validate_transition(&req.task.id, existing.state, req.task.state) {
+            warn!("Rejected upsert: {}", e.message);
+            return Err(e.into());
+        }
+    }
     
     // Check if project with same repo_path already exists
+    let mut project_changed = false;
     let project_id = if let Some((existing_id, _)) = state.projects
         .iter()
         .find(|(_, p)| p.repo_path == req.project.repo_path)
@@ -47,9 +142,10 @@ pub async fn upsert_task(
         // Update existing project's preferred_ide if provided
         if let Some(existing_project) = state.projects.get_mut(&existing_id) {
             if let Some(new_ide) = req.project.preferred_ide.clone() {
-                if !new_ide.is_empty() {
+                if !new_ide.is_empty() && new_ide != existing_project.preferred_ide {
                     existing_project.preferred_ide = new_ide;
                     existing_project.updated_at = now;
+                    project_changed = true;
                 }
             }
         }
@@ -67,45 +163,67 @@ pub async fn upsert_task(
             updated_at: now,
         };
         state.projects.insert(new_id.clone(), project);
+        project_changed = true;
         new_id
     };
 
     // Create or update task (preserve existing pinned status if task exists)
-    let existing_pinned = state.tasks.get(&req.task.id).map(|t| t.pinned).unwrap_or(false);
+    let existing_task = state.tasks.get(&req.task.id).cloned();
+    let existing_pinned = existing_task.as_ref().map(|t| t.pinned).unwrap_or(false);
     let task = Task {
         id: req.task.id.clone(),
         project_id,
         agent: req.task.agent.clone(),  // Clone to avoid move
         title: req.task.title,
-        state: req.task.state.clone(),
+        state: req.task.state,
         details: req.task.details.clone(),
         created_at: now,
         updated_at: now,
         pinned: existing_pinned,
         detection_method: None, // Initial task creation - no detection method yet
+        last_heartbeat: existing_task.as_ref().and_then(|t| t.last_heartbeat),
     };
     state.tasks.insert(req.task.id.clone(), task.clone());
     state.updated_at = now;
 
-    // Emit event to frontend
-    let _ = app_handle.emit("tasks-updated", &state.clone());
+    // Emit events to frontend
+    if project_changed {
+        if let Some(project) = state.projects.get(&task.project_id).cloned() {
+            let revision = state.next_revision();
+            emit_task_event(ctx.events.as_ref(), TaskEvent::ProjectUpdated { revision, project });
+        }
+    }
+    let previous_state = existing_task.as_ref().map(|t| t.state);
+    let transition = match previous_state {
+        Some(previous) if previous == task.state => None,
+        previous => Some(transition_for(&task, previous, req.task.source.clone())),
+    };
+    let revision = state.next_revision();
+    let event = match existing_task {
+        Some(existing) => TaskEvent::TaskUpdated {
+            revision,
+            task_id: task.id.clone(),
+            changes: task_changes(&existing, &task),
+        },
+        None => TaskEvent::TaskCreated { revision, task: task.clone() },
+    };
+    emit_task_event(ctx.events.as_ref(), event);
 
-    // Send notification only for PENDING and ERROR states
-    if req.task.state == "PENDING" || req.task.state == "ERROR" {
-        let project_name = req.project.name.clone();
-        let notification_data = serde_json::json!({
-            "title": format!("{} - {}", project_name, req.task.agent),
-            "body": req.task.state
-        });
-        let _ = app_handle.emit("show-notification", &notification_data);
+    // Let the notification rules decide whether to notify, and tell the webhooks
+    on_task_update(&ctx, &task, state.projects.get(&task.project_id), previous_state);
+    if let Some(transition) = &transition {
+        on_transition(transition, &task, state.projects.get(&task.project_id));
     }
     
-    // Update tray menu
-    drop(state); // Release the lock before calling update_tray_menu
-    crate::tray::update_tray_menu(&app_handle);
+    // Let the event sink refresh the tray
+    drop(state); // Release the lock before notifying the event sink
+    ctx.events.state_changed();
 
     // Save state to disk
-    if let Err(e) = save_app_state() {
+    if let Some(transition) = transition {
+        record_transition(&transition);
+    }
+    if let Err(e) = persist_state(&ctx.state) {
         error!("Failed to save app state: {e}");
     }
 
@@ -121,27 +239,33 @@ fn has_claude_code_hooks(repo_path: &str) -> bool {
 /// POST /v1/tasks/state - Update task state
 pub async fn update_task_state(
     headers: HeaderMap,
-    AxumState(app_handle): AxumState<AppHandle>,
-    Json(req): Json<StateUpdateRequest>,
-) -> Result<Json<()>, StatusCode> {
+    AxumState(ctx): AxumState<ApiContext>,
+    Json(mut req): Json<StateUpdateRequest>,
+) -> Result<Json<()>, ApiError> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Report) {
         warn!("Unauthorized access attempt to /v1/tasks/state");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status.into());
     }
-    let mut state = APP_STATE.lock();
+    redact_details(&req.task_id, &mut req.details);
+    let mut state = ctx.state.lock();
     
     // Check if task exists and collect needed data
-    let (project_name, agent_name, repo_path) = if let Some(task) = state.tasks.get(&req.task_id) {
+    let repo_path = if let Some(task) = state.tasks.get(&req.task_id) {
+        if let Err(e) = validate_transition(&req.task_id, task.state, req.state) {
+            warn!("Rejected state update: {}", e.message);
+            return Err(e.into());
+        }
+
         if let Some(project) = state.projects.get(&task.project_id) {
-            (project.name.clone(), task.agent.clone(), project.repo_path.clone())
+            project.repo_path.clone()
         } else {
             warn!("Project not found for task {}", req.task_id);
-            ("Unknown".to_string(), "Unknown".to_string(), String::new())
+            String::new()
         }
     } else {
         warn!("Task not found for state update: {}", req.task_id);
-        return Err(StatusCode::NOT_FOUND);
+        return Err(StatusCode::NOT_FOUND.into());
     };
 
     // Determine detection method based on source and hook configuration
@@ -165,32 +289,42 @@ pub async fn update_task_state(
           req.details.as_deref().unwrap_or("no details"));
 
     // Update the task state
+    let mut transition = None;
     if let Some(task) = state.tasks.get_mut(&req.task_id) {
-        task.state = req.state.clone();
+        let before = task.clone();
+        let before_state = before.state;
+        task.state = req.state;
         task.details = req.details.clone();
         task.detection_method = Some(detection_method);
         task.updated_at = current_timestamp();
+        let changes = task_changes(&before, task);
+        if before.state != task.state {
+            transition = Some(transition_for(task, Some(before.state), req.source.clone()));
+        }
         state.updated_at = current_timestamp();
 
         // Emit event to frontend
-        let _ = app_handle.emit("tasks-updated", &state.clone());
-
-        // Send notification only for PENDING and ERROR states
-        if req.state == "PENDING" || req.state == "ERROR" {
-            let notification_data = serde_json::json!({
-                "title": format!("{} - {}", project_name, agent_name),
-                "body": req.state
-            });
-            let _ = app_handle.emit("show-notification", &notification_data);
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskUpdated { revision, task_id: req.task_id.clone(), changes });
+
+        // Let the notification rules decide whether to notify, and tell the webhooks
+        if let Some(task) = state.tasks.get(&req.task_id) {
+            on_task_update(&ctx, task, state.projects.get(&task.project_id), Some(before_state));
+            if let Some(transition) = &transition {
+                on_transition(transition, task, state.projects.get(&task.project_id));
+            }
         }
     }
     
-    // Update tray menu
-    drop(state); // Release the lock before calling update_tray_menu
-    crate::tray::update_tray_menu(&app_handle);
+    // Let the event sink refresh the tray
+    drop(state); // Release the lock before notifying the event sink
+    ctx.events.state_changed();
 
     // Save state to disk
-    if let Err(e) = save_app_state() {
+    if let Some(transition) = transition {
+        record_transition(&transition);
+    }
+    if let Err(e) = persist_state(&ctx.state) {
         error!("Failed to save app state: {e}");
     }
 
@@ -200,27 +334,32 @@ pub async fn update_task_state(
 /// POST /v1/tasks/details - Update task details
 pub async fn update_task_details(
     headers: HeaderMap,
-    AxumState(app_handle): AxumState<AppHandle>,
+    AxumState(ctx): AxumState<ApiContext>,
     Json(req): Json<DetailsUpdateRequest>,
 ) -> Result<Json<()>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Report) {
         warn!("Unauthorized access attempt to /v1/tasks/details");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
-    let mut state = APP_STATE.lock();
+    let mut details = Some(req.details);
+    redact_details(&req.task_id, &mut details);
+    let mut state = ctx.state.lock();
     
     if let Some(task) = state.tasks.get_mut(&req.task_id) {
-        task.details = Some(req.details);
+        let before = task.clone();
+        task.details = details;
         task.updated_at = current_timestamp();
+        let changes = task_changes(&before, task);
         state.updated_at = current_timestamp();
 
         // Emit event to frontend
-        let _ = app_handle.emit("tasks-updated", &state.clone());
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskUpdated { revision, task_id: req.task_id.clone(), changes });
         
         // Save state to disk
         drop(state); // Release the lock before calling save_app_state
-        if let Err(e) = save_app_state() {
+        if let Err(e) = persist_state(&ctx.state) {
             error!("Failed to save app state: {e}");
         }
     }
@@ -231,34 +370,51 @@ pub async fn update_task_details(
 /// POST /v1/tasks/done - Mark task as done
 pub async fn mark_task_done(
     headers: HeaderMap,
-    AxumState(app_handle): AxumState<AppHandle>,
-    Json(req): Json<TaskDoneRequest>,
-) -> Result<Json<()>, StatusCode> {
+    AxumState(ctx): AxumState<ApiContext>,
+    Json(mut req): Json<TaskDoneRequest>,
+) -> Result<Json<()>, ApiError> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Report) {
         warn!("Unauthorized access attempt to /v1/tasks/done");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status.into());
     }
-    let mut state = APP_STATE.lock();
+    redact_details(&req.task_id, &mut req.details);
+    let mut state = ctx.state.lock();
     
     if let Some(task) = state.tasks.get_mut(&req.task_id) {
-        task.state = "DONE".to_string();
+        if let Err(e) = validate_transition(&req.task_id, task.state, TaskState::Done) {
+            warn!("Rejected done request: {}", e.message);
+            return Err(e.into());
+        }
+
+        let before = task.clone();
+        task.state = TaskState::Done;
         task.details = req.details;
         task.updated_at = current_timestamp();
         let task_title = task.title.clone();
+        let changes = task_changes(&before, task);
+        let transition = (before.state != task.state)
+            .then(|| transition_for(task, Some(before.state), req.source.clone()));
         state.updated_at = current_timestamp();
 
         info!("Marked task as done: {} ({})", task_title, req.task_id);
 
         // Emit event to frontend
-        let _ = app_handle.emit("tasks-updated", &state.clone());
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskUpdated { revision, task_id: req.task_id.clone(), changes });
+        if let (Some(transition), Some(task)) = (&transition, state.tasks.get(&req.task_id)) {
+            on_transition(transition, task, state.projects.get(&task.project_id));
+        }
         
-        // Update tray menu
-        drop(state); // Release the lock before calling update_tray_menu
-        crate::tray::update_tray_menu(&app_handle);
+        // Let the event sink refresh the tray
+        drop(state); // Release the lock before notifying the event sink
+        ctx.events.state_changed();
 
         // Save state to disk
-        if let Err(e) = save_app_state() {
+        if let Some(transition) = transition {
+            record_transition(&transition);
+        }
+        if let Err(e) = persist_state(&ctx.state) {
             error!("Failed to save app state: {e}");
         }
     }
@@ -266,56 +422,121 @@ pub async fn mark_task_done(
     Ok(Json(()))
 }
 
-/// DELETE /v1/tasks/:id - Delete task
+/// POST /v1/tasks/delete - Remove a task from the dashboard; it is kept in the archive
 pub async fn delete_task(
     headers: HeaderMap,
-    AxumState(app_handle): AxumState<AppHandle>,
+    AxumState(ctx): AxumState<ApiContext>,
     Json(req): Json<TaskDeleteRequest>,
 ) -> Result<Json<()>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Admin) {
         warn!("Unauthorized access attempt to /v1/tasks/delete");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
-    let mut state = APP_STATE.lock();
-    
-    if state.tasks.remove(&req.task_id).is_some() {
-        state.updated_at = current_timestamp();
-        info!("Deleted task: {}", req.task_id);
 
-        // Emit event to frontend
-        let _ = app_handle.emit("tasks-updated", &state.clone());
-        
-        // Update tray menu
-        drop(state); // Release the lock before calling update_tray_menu
-        crate::tray::update_tray_menu(&app_handle);
+    match archive::archive_task(&ctx, &req.task_id, current_timestamp()) {
+        Ok(Some(_)) => Ok(Json(())),
+        Ok(None) => {
+            warn!("Task not found for delete: {}", req.task_id);
+            Err(StatusCode::NOT_FOUND)
+        }
+        Err(e) => {
+            error!("Failed to archive task {}: {e}", req.task_id);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
 
-        // Save state to disk
-        if let Err(e) = save_app_state() {
-            error!("Failed to save app state: {e}");
+/// POST /v1/tasks/archive - Move a task off the dashboard into the archive
+pub async fn archive_task(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+    Json(req): Json<TaskArchiveRequest>,
+) -> Result<Json<()>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/tasks/archive");
+        return Err(status);
+    }
+
+    match archive::archive_task(&ctx, &req.task_id, current_timestamp()) {
+        Ok(Some(_)) => Ok(Json(())),
+        Ok(None) => {
+            warn!("Task not found for archive: {}", req.task_id);
+            Err(StatusCode::NOT_FOUND)
+        }
+        Err(e) => {
+            error!("Failed to archive task {}: {e}", req.task_id);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
-    
-    Ok(Json(()))
+}
+
+/// POST /v1/tasks/restore - Put an archived task back on the dashboard
+pub async fn restore_task(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+    Json(req): Json<TaskArchiveRequest>,
+) -> Result<Json<Task>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/tasks/restore");
+        return Err(status);
+    }
+
+    match archive::restore_task(&ctx, &req.task_id, current_timestamp()) {
+        Ok(Restore::Restored(task)) => Ok(Json(task)),
+        Ok(Restore::NotArchived) => {
+            warn!("Task not found in archive: {}", req.task_id);
+            Err(StatusCode::NOT_FOUND)
+        }
+        Ok(Restore::AlreadyLive) => {
+            warn!("Cannot restore {}: a live task has the same id", req.task_id);
+            Err(StatusCode::CONFLICT)
+        }
+        Err(e) => {
+            error!("Failed to restore task {}: {e}", req.task_id);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// GET /v1/archive - Search archived tasks with `?q=`, most recently archived first
+pub async fn search_archive(
+    headers: HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<ArchiveQuery>,
+) -> Result<Json<Vec<ArchivedTask>>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Read) {
+        warn!("Unauthorized access attempt to /v1/archive");
+        return Err(status);
+    }
+
+    archive::search(query.q.as_deref(), query.limit).map(Json).map_err(|e| {
+        error!("Failed to search archive: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
 }
 
 /// POST /v1/tasks/pin - Pin/unpin task
 pub async fn pin_task(
     headers: HeaderMap,
-    AxumState(app_handle): AxumState<AppHandle>,
+    AxumState(ctx): AxumState<ApiContext>,
     Json(req): Json<TaskPinRequest>,
 ) -> Result<Json<()>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Admin) {
         warn!("Unauthorized access attempt to /v1/tasks/pin");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
-    let mut state = APP_STATE.lock();
+    let mut state = ctx.state.lock();
     
     if let Some(task) = state.tasks.get_mut(&req.task_id) {
+        let before = task.clone();
         task.pinned = req.pinned;
         task.updated_at = current_timestamp();
         let task_title = task.title.clone();
+        let changes = task_changes(&before, task);
         state.updated_at = current_timestamp();
 
         info!("{} task: {} ({})", 
@@ -323,11 +544,12 @@ pub async fn pin_task(
               task_title, req.task_id);
 
         // Emit event to frontend
-        let _ = app_handle.emit("tasks-updated", &state.clone());
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskUpdated { revision, task_id: req.task_id.clone(), changes });
         
         // Save state to disk
         drop(state); // Release the lock before calling save_app_state
-        if let Err(e) = save_app_state() {
+        if let Err(e) = persist_state(&ctx.state) {
             error!("Failed to save app state: {e}");
         }
     }
@@ -335,12 +557,263 @@ pub async fn pin_task(
     Ok(Json(()))
 }
 
+/// POST /v1/tasks/snooze - Silence a task's notifications for a while
+pub async fn snooze_task_notifications(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+    Json(req): Json<TaskSnoozeRequest>,
+) -> Result<Json<()>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/tasks/snooze");
+        return Err(status);
+    }
+
+    if !ctx.state.lock().tasks.contains_key(&req.task_id) {
+        warn!("Task not found for snooze: {}", req.task_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+    snooze_task(&req.task_id, req.minutes);
+
+    Ok(Json(()))
+}
+
+/// POST /v1/tasks/heartbeat - Tell the server a task's wrapper is still running
+pub async fn heartbeat_task(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+    Json(req): Json<HeartbeatRequest>,
+) -> Result<Json<()>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Report) {
+        warn!("Unauthorized access attempt to /v1/tasks/heartbeat");
+        return Err(status);
+    }
+
+    match heartbeat::beat(&ctx, &req.task_id, req.state, current_timestamp()) {
+        Heartbeat::Alive | Heartbeat::Revived => Ok(Json(())),
+        Heartbeat::UnknownTask => {
+            warn!("Task not found for heartbeat: {}", req.task_id);
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// GET /v1/tasks/{task_id}/history - Recorded state transitions for a task
+pub async fn get_task_history(
+    headers: HeaderMap,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<Vec<StateTransition>>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Read) {
+        warn!("Unauthorized access attempt to /v1/tasks/{task_id}/history");
+        return Err(status);
+    }
+
+    debug!("Returning state history for task: {task_id}");
+    task_history(&task_id).map(Json).map_err(|e| {
+        error!("Failed to load history for task {task_id}: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// GET /v1/webhooks/deliveries - Recent webhook deliveries, newest first
+pub async fn get_webhook_deliveries(
+    headers: HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<DeliveryQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Read) {
+        warn!("Unauthorized access attempt to /v1/webhooks/deliveries");
+        return Err(status);
+    }
+
+    recent_deliveries(query.limit.unwrap_or(50).min(500)).map(Json).map_err(|e| {
+        error!("Failed to load webhook deliveries: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// GET /v1/tokens - Named API tokens (without their secrets)
+pub async fn list_tokens(headers: HeaderMap) -> Result<Json<Vec<ApiToken>>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/tokens");
+        return Err(status);
+    }
+
+    tokens::list().map(Json).map_err(|e| {
+        error!("Failed to list API tokens: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// GET /v1/audit - Rejected requests, token usage and rate-limited peers
+pub async fn get_auth_audit(
+    headers: HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<AuditQuery>,
+) -> Result<Json<AuthAudit>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/audit");
+        return Err(status);
+    }
+
+    audit::report(query.limit).map(Json).map_err(|e| {
+        error!("Failed to load auth audit: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// POST /v1/tokens - Create a named token; the secret is only returned here
+pub async fn create_token(
+    headers: HeaderMap,
+    Json(req): Json<TokenCreateRequest>,
+) -> Result<Json<IssuedToken>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/tokens");
+        return Err(status);
+    }
+    if let Err(e) = tokens::validate_name(&req.name) {
+        warn!("Rejected token request: {e}");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match tokens::create(&req.name, req.scope, current_timestamp()) {
+        Ok(Some(issued)) => Ok(Json(issued)),
+        Ok(None) => {
+            warn!("A token named '{}' already exists", req.name);
+            Err(StatusCode::CONFLICT)
+        }
+        Err(e) => {
+            error!("Failed to create API token: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// POST /v1/tokens/rotate - Issue a new secret for a named token
+pub async fn rotate_token(
+    headers: HeaderMap,
+    Json(req): Json<TokenNameRequest>,
+) -> Result<Json<IssuedToken>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/tokens/rotate");
+        return Err(status);
+    }
+
+    match tokens::rotate(&req.name, current_timestamp()) {
+        Ok(Some(issued)) => Ok(Json(issued)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to rotate API token: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// POST /v1/tokens/revoke - Delete a named token
+pub async fn revoke_token(
+    headers: HeaderMap,
+    Json(req): Json<TokenNameRequest>,
+) -> Result<Json<()>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/tokens/revoke");
+        return Err(status);
+    }
+
+    match tokens::revoke(&req.name) {
+        Ok(true) => Ok(Json(())),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to revoke API token: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// GET /v1/stats - Time-in-state analytics per project, agent and day
+pub async fn get_stats(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+    axum::extract::Query(query): axum::extract::Query<StatsQuery>,
+) -> Result<Json<StatsReport>, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Read) {
+        warn!("Unauthorized access attempt to /v1/stats");
+        return Err(status);
+    }
+
+    if let (Some(since), Some(until)) = (query.since, query.until) {
+        if since >= until {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    crate::analytics::collect_stats(&ctx.state, query.since, query.until).map(Json).map_err(|e| {
+        error!("Failed to compute stats: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// GET /v1/reports/{period} - Daily or weekly digest as JSON, or as Markdown with `?format=markdown`
+pub async fn get_report(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+    axum::extract::Path(period): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<ReportQuery>,
+) -> Result<Response, StatusCode> {
+    // Validate authentication
+    if let Err(status) = authorize(&headers, Access::Read) {
+        warn!("Unauthorized access attempt to /v1/reports/{period}");
+        return Err(status);
+    }
+
+    let period: ReportPeriod = period.parse().map_err(|e| {
+        warn!("Rejected report request: {e}");
+        StatusCode::BAD_REQUEST
+    })?;
+    let report = crate::reports::build_report(&ctx.state, period, query.until).map_err(|e| {
+        error!("Failed to build {} report: {e}", period.as_str());
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    match query.format.as_deref() {
+        None | Some("json") => Ok(Json(report).into_response()),
+        Some("markdown" | "md") => Ok((
+            [(axum::http::header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            crate::reports::render_markdown(&report),
+        ).into_response()),
+        Some(_) => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// GET /metrics - Prometheus text exposition of task state, transitions and notifications
+pub async fn get_metrics(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+) -> Result<Response, StatusCode> {
+    // Accept the main token or the read-only metrics token
+    if let Err(status) = authorize_metrics(&headers) {
+        warn!("Unauthorized access attempt to /metrics");
+        return Err(status);
+    }
+
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        crate::metrics::render(&ctx.state),
+    ).into_response())
+}
+
 /// GET /v1/setup/status - Get setup status
 pub async fn get_setup_status(headers: HeaderMap) -> Result<Json<SetupStatus>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Any) {
         warn!("Unauthorized access attempt to /v1/setup/status");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
     
     let is_first_launch = !crate::utils::get_setup_completion_flag();
@@ -357,16 +830,19 @@ pub async fn get_setup_status(headers: HeaderMap) -> Result<Json<SetupStatus>, S
 }
 
 /// GET /v1/health - Health check endpoint
-pub async fn health_check(headers: HeaderMap) -> Result<Json<serde_json::Value>, StatusCode> {
+pub async fn health_check(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Any) {
         warn!("Unauthorized access attempt to /v1/health");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
     
     // Update last CLI ping timestamp
     let current_time = current_timestamp();
-    let mut state = APP_STATE.lock();
+    let mut state = ctx.state.lock();
     state.last_cli_ping = Some(current_time);
     info!("Health check: Updated last_cli_ping to {current_time}");
     
@@ -379,8 +855,10 @@ pub async fn health_check(headers: HeaderMap) -> Result<Json<serde_json::Value>,
     
     drop(state); // Release lock before saving
     
-    // Save state to persist CLI ping
-    if let Err(e) = save_app_state() {
+    // Persist only the CLI ping rather than the whole state
+    if let Err(e) = crate::store::with_connection(|conn| {
+        crate::store::save_meta_value(conn, "last_cli_ping", Some(current_time))
+    }) {
         error!("Failed to save app state after health check: {e}");
     }
     
@@ -390,16 +868,17 @@ pub async fn health_check(headers: HeaderMap) -> Result<Json<serde_json::Value>,
 /// GET /v1/debug/patterns/:task_id - Get debug patterns for specific task
 pub async fn get_debug_patterns_for_task(
     headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
     axum::extract::Path(task_id): axum::extract::Path<String>,
 ) -> Result<Json<DebugData>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Admin) {
         warn!("Unauthorized access attempt to /v1/debug/patterns/{task_id}");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
     
     debug!("Returning debug patterns for task: {task_id}");
-    let state = APP_STATE.lock();
+    let state = ctx.state.lock();
     
     match state.debug_data.get(&task_id) {
         Some(debug_data) => Ok(Json(debug_data.clone())),
@@ -407,12 +886,13 @@ pub async fn get_debug_patterns_for_task(
             // Return empty debug data structure
             let empty_debug = DebugData {
                 cleaned_buffer: String::new(),
-                current_state: "IDLE".to_string(),
+                current_state: TaskState::Idle.to_string(),
                 detection_history: Vec::new(),
                 task_id: task_id.clone(),
                 pattern_tests: None,
                 confidence: None,
                 is_active: None,
+                redactions: 0,
             };
             Ok(Json(empty_debug))
         }
@@ -420,9 +900,18 @@ pub async fn get_debug_patterns_for_task(
 }
 
 /// GET /v1/debug/patterns - Get most recent debug patterns
-pub async fn get_debug_patterns(_headers: HeaderMap) -> Result<Json<DebugData>, StatusCode> {
+pub async fn get_debug_patterns(
+    headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
+) -> Result<Json<DebugData>, StatusCode> {
+    // Validate authentication: terminal buffers can hold anything the agent printed
+    if let Err(status) = authorize(&headers, Access::Admin) {
+        warn!("Unauthorized access attempt to /v1/debug/patterns");
+        return Err(status);
+    }
+
     debug!("Returning most recent debug patterns");
-    let state = APP_STATE.lock();
+    let state = ctx.state.lock();
     
     // Find the most recent debug data entry (highest timestamp)
     let most_recent = state.debug_data
@@ -441,12 +930,13 @@ pub async fn get_debug_patterns(_headers: HeaderMap) -> Result<Json<DebugData>,
             // Return empty debug data structure
             let empty_debug = DebugData {
                 cleaned_buffer: String::new(),
-                current_state: "IDLE".to_string(),
+                current_state: TaskState::Idle.to_string(),
                 detection_history: Vec::new(),
                 task_id: "none".to_string(),
                 pattern_tests: None,
                 confidence: None,
                 is_active: None,
+                redactions: 0,
             };
             Ok(Json(empty_debug))
         }
@@ -456,20 +946,24 @@ pub async fn get_debug_patterns(_headers: HeaderMap) -> Result<Json<DebugData>,
 /// POST /v1/debug/update - Update debug data
 pub async fn update_debug_data(
     headers: HeaderMap,
+    AxumState(ctx): AxumState<ApiContext>,
     Json(req): Json<DebugUpdateRequest>,
 ) -> Result<Json<()>, StatusCode> {
     // Validate authentication
-    if !validate_auth_header(&headers) {
+    if let Err(status) = authorize(&headers, Access::Report) {
         warn!("Unauthorized access attempt to /v1/debug/update");
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(status);
     }
-    let mut state = APP_STATE.lock();
-    let task_id = req.debug_data.task_id.clone();
-    state.debug_data.insert(task_id, req.debug_data);
+    let mut debug_data = req.debug_data;
+    // Mask secrets before trimming, so a secret cut in half by the trim is still caught
+    redact_debug_data(&mut debug_data);
+    crate::retention::trim_debug_data(&mut debug_data);
+    let mut state = ctx.state.lock();
+    state.debug_data.insert(debug_data.task_id.clone(), debug_data.clone());
     
-    // Save to disk
-    drop(state); // Release lock before calling save_app_state
-    if let Err(e) = save_app_state() {
+    // Save only this task's debug data
+    drop(state); // Release lock before writing to the database
+    if let Err(e) = crate::store::with_connection(|conn| crate::store::save_debug_data(conn, &debug_data)) {
         error!("Failed to save debug data: {e}");
     }
     
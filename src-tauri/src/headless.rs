@@ -0,0 +1,112 @@
+use std::{io::Write, sync::Arc};
+use log::{error, info, warn};
+use serde_json::Value;
+use crate::api::ApiContext;
+use crate::events::EventSink;
+use crate::settings::read_headless_settings;
+use crate::state::{initialize_app_state, save_app_state, APP_STATE};
+use crate::types::AppSettings;
+use crate::utils::{current_timestamp, setup_logging};
+
+/// Writes each event to stdout as one JSON line, for running without a webview
+pub struct StdoutEventSink;
+
+impl EventSink for StdoutEventSink {
+    fn emit_event(&self, event: &str, payload: &Value) {
+        let line = serde_json::json!({
+            "event": event,
+            "timestamp": current_timestamp(),
+            "payload": payload,
+        });
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{line}");
+        let _ = stdout.flush();
+    }
+}
+
+/// Load the settings file and start what the desktop app starts around the API: subsystems
+/// configured from settings and the background jobs. Returns the settings applied.
+pub fn start(ctx: &ApiContext) -> AppSettings {
+    let settings = read_headless_settings().unwrap_or_else(|e| {
+        warn!("Failed to load settings, using defaults: {e}");
+        AppSettings::default()
+    });
+    crate::services::start(&settings, ctx);
+    settings
+}
+
+/// Run the tracking core (state store, auth and HTTP API) without tray or window.
+/// Settings come from the desktop app's `settings.json` (or `TALLR_SETTINGS`), and the listen
+/// address from settings or `TALLR_ADDR` / `TALLR_PORT` / `TALLR_SOCKET`.
+pub fn run_headless() {
+    if let Err(e) = setup_logging() {
+        eprintln!("Failed to setup logging: {e}");
+    }
+
+    info!("Tallr starting in headless mode");
+
+    if let Err(e) = initialize_app_state() {
+        warn!("Failed to initialize app state: {e}");
+    }
+
+    match crate::auth::get_or_create_auth_token() {
+        Ok(_) => info!("Auth token initialized successfully"),
+        Err(e) => warn!("Failed to initialize auth token: {e}")
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!("Failed to start async runtime: {e}");
+            eprintln!("Failed to start async runtime: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let server_failed = runtime.block_on(async {
+        let ctx = ApiContext::new(APP_STATE.clone(), Arc::new(StdoutEventSink));
+        let settings = start(&ctx);
+
+        tokio::select! {
+            _ = crate::services::serve(&settings, ctx) => {
+                error!("HTTP server stopped");
+                true
+            }
+            _ = shutdown_signal() => {
+                info!("Shutdown requested");
+                false
+            }
+        }
+    });
+
+    // Handlers persist as they go; this catches anything in flight at shutdown
+    if let Err(e) = save_app_state() {
+        error!("Failed to save app state on shutdown: {e}");
+    }
+
+    if server_failed {
+        eprintln!("Tallr HTTP server stopped; see the log file for details");
+        std::process::exit(1);
+    }
+}
+
+/// Resolves on Ctrl+C, or SIGTERM on Unix (e.g. from systemd)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate => {}
+    }
+}
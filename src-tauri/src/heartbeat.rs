@@ -0,0 +1,228 @@
+use std::{collections::HashMap, time::Duration};
+use log::{error, info};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use crate::api::ApiContext;
+use crate::events::{emit_task_event, task_changes};
+use crate::history::{record_transition, transition_for};
+use crate::notifications::on_task_update;
+use crate::state::persist_state;
+use crate::store;
+use crate::types::{AppSettings, HeartbeatSettings, StateTransition, Task, TaskEvent, TaskState};
+use crate::utils::current_timestamp;
+use crate::webhooks::on_transition;
+
+/// How often tasks are checked for missed heartbeats
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+static SETTINGS: Lazy<RwLock<HeartbeatSettings>> = Lazy::new(Default::default);
+
+// State each STALE task was in before it went quiet, restored when its wrapper is heard from again.
+// After a restart this is gone and a revived task takes the state its wrapper reports.
+static STALE_FROM: Lazy<Mutex<HashMap<String, TaskState>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Result of a heartbeat from a wrapper
+#[derive(Debug)]
+pub enum Heartbeat {
+    Alive,
+    /// The task had been marked STALE and is back in its earlier state
+    Revived,
+    UnknownTask,
+}
+
+/// Load the stale-session settings
+pub fn configure(settings: &AppSettings) {
+    let heartbeat = settings.heartbeat.clone();
+    info!("Stale session detection {}: STALE after {}s without a heartbeat",
+          if heartbeat.enabled { "enabled" } else { "disabled" }, heartbeat.stale_after_seconds);
+    *SETTINGS.write() = heartbeat;
+}
+
+/// Refresh the tray, record the transitions and save the state, once the state lock is released
+fn publish(ctx: &ApiContext, transitions: Vec<StateTransition>) {
+    ctx.events.state_changed();
+    for transition in &transitions {
+        record_transition(transition);
+    }
+    if let Err(e) = persist_state(&ctx.state) {
+        error!("Failed to save app state: {e}");
+    }
+}
+
+/// State a STALE task resumes: the open state its wrapper reports, else the one it was in
+/// before it went quiet (unknown after a restart), else IDLE
+fn resumed_state(reported: Option<TaskState>, previous: Option<TaskState>) -> TaskState {
+    reported
+        .filter(|reported| !reported.is_terminal() && *reported != TaskState::Stale)
+        .or(previous)
+        .unwrap_or(TaskState::Idle)
+}
+
+/// Note that the task's wrapper is alive. A STALE task resumes the state it was in before
+/// (or `reported`, the state the wrapper says it is in); otherwise only the timestamp is saved.
+pub fn beat(ctx: &ApiContext, task_id: &str, reported: Option<TaskState>, now: i64) -> Heartbeat {
+    let mut state = ctx.state.lock();
+    let Some(task) = state.tasks.get_mut(task_id) else {
+        return Heartbeat::UnknownTask;
+    };
+    task.last_heartbeat = Some(now);
+    if task.state != TaskState::Stale {
+        drop(state);
+        if let Err(e) = store::with_connection(|conn| store::save_task_heartbeat(conn, task_id, now)) {
+            error!("{e}");
+        }
+        return Heartbeat::Alive;
+    }
+
+    let resumed = resumed_state(reported, STALE_FROM.lock().remove(task_id));
+    let before = task.clone();
+    task.state = resumed;
+    task.updated_at = now;
+    let task = task.clone();
+    let transition = transition_for(&task, Some(TaskState::Stale), Some("heartbeat".to_string()));
+    state.updated_at = now;
+    info!("Task {task_id} is sending heartbeats again; back to {resumed}");
+
+    let revision = state.next_revision();
+    emit_task_event(ctx.events.as_ref(), TaskEvent::TaskUpdated {
+        revision,
+        task_id: task_id.to_string(),
+        changes: task_changes(&before, &task),
+    });
+    let project = state.projects.get(&task.project_id);
+    on_task_update(ctx, &task, project, Some(TaskState::Stale));
+    on_transition(&transition, &task, project);
+    drop(state);
+
+    publish(ctx, vec![transition]);
+    Heartbeat::Revived
+}
+
+/// Whether the task's wrapper has missed its heartbeats for longer than allowed at `now`.
+/// Tasks that never sent a heartbeat (e.g. reported only by hooks) are left alone.
+fn gone_quiet(settings: &HeartbeatSettings, task: &Task, now: i64) -> bool {
+    settings.enabled
+        && settings.stale_after_seconds > 0
+        && !task.state.is_terminal()
+        && task.state != TaskState::Stale
+        && task.last_heartbeat.is_some_and(|beat| now - beat >= settings.stale_after_seconds as i64)
+}
+
+/// Mark every task whose wrapper has gone quiet as STALE. Returns how many were marked.
+pub fn check(ctx: &ApiContext, now: i64) -> usize {
+    let settings = SETTINGS.read().clone();
+    let mut state = ctx.state.lock();
+    let mut stale_from = STALE_FROM.lock();
+
+    // Forget tasks that were revived by a state update or removed
+    stale_from.retain(|task_id, _| state.tasks.get(task_id).is_some_and(|task| task.state == TaskState::Stale));
+
+    let quiet: Vec<String> = state.tasks.values()
+        .filter(|task| gone_quiet(&settings, task, now))
+        .map(|task| task.id.clone())
+        .collect();
+    let mut transitions = Vec::with_capacity(quiet.len());
+    for task_id in quiet {
+        let Some(task) = state.tasks.get_mut(&task_id) else { continue };
+        let before = task.clone();
+        task.state = TaskState::Stale;
+        task.updated_at = now;
+        let task = task.clone();
+        info!("No heartbeat from task {task_id} for {}s; marking it STALE (was {})",
+              now - task.last_heartbeat.unwrap_or(now), before.state);
+        stale_from.insert(task_id.clone(), before.state);
+
+        let transition = transition_for(&task, Some(before.state), Some("reaper".to_string()));
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskUpdated {
+            revision,
+            task_id,
+            changes: task_changes(&before, &task),
+        });
+        on_transition(&transition, &task, state.projects.get(&task.project_id));
+        transitions.push(transition);
+    }
+    drop(stale_from);
+
+    let marked = transitions.len();
+    if marked > 0 {
+        state.updated_at = now;
+    }
+    drop(state);
+    if marked > 0 {
+        publish(ctx, transitions);
+    }
+    marked
+}
+
+/// Background reaper; runs for the life of the HTTP server
+pub async fn run(ctx: ApiContext) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        check(&ctx, current_timestamp());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A task in `state` whose wrapper last sent a heartbeat at `last_heartbeat`
+    fn task(state: TaskState, last_heartbeat: Option<i64>) -> Task {
+        Task {
+            id: "task".to_string(),
+            project_id: "project".to_string(),
+            agent: "claude".to_string(),
+            title: "Refactor billing".to_string(),
+            state,
+            details: None,
+            created_at: 0,
+            updated_at: 0,
+            pinned: false,
+            detection_method: None,
+            last_heartbeat,
+        }
+    }
+
+    fn settings(stale_after_seconds: u64) -> HeartbeatSettings {
+        HeartbeatSettings { enabled: true, stale_after_seconds }
+    }
+
+    #[test]
+    fn tasks_go_quiet_once_the_timeout_has_passed() {
+        let working = task(TaskState::Working, Some(1000));
+        assert!(!gone_quiet(&settings(90), &working, 1089));
+        assert!(gone_quiet(&settings(90), &working, 1090));
+        assert!(gone_quiet(&settings(90), &task(TaskState::Pending, Some(1000)), 5000));
+    }
+
+    #[test]
+    fn some_tasks_are_never_marked_stale() {
+        let settings = settings(90);
+        assert!(!gone_quiet(&settings, &task(TaskState::Working, None), 5000), "never sent a heartbeat");
+        assert!(!gone_quiet(&settings, &task(TaskState::Stale, Some(1000)), 5000), "already stale");
+        assert!(!gone_quiet(&settings, &task(TaskState::Done, Some(1000)), 5000));
+        assert!(!gone_quiet(&settings, &task(TaskState::Cancelled, Some(1000)), 5000));
+    }
+
+    #[test]
+    fn disabled_or_zero_timeouts_never_mark_tasks_stale() {
+        let working = task(TaskState::Working, Some(1000));
+        assert!(!gone_quiet(&settings(0), &working, 5000));
+        assert!(!gone_quiet(&HeartbeatSettings { enabled: false, stale_after_seconds: 90 }, &working, 5000));
+    }
+
+    #[test]
+    fn revived_tasks_prefer_the_reported_state_then_the_one_before() {
+        use TaskState::*;
+        assert_eq!(resumed_state(Some(Pending), Some(Working)), Pending);
+        assert_eq!(resumed_state(None, Some(Working)), Working);
+        assert_eq!(resumed_state(None, None), Idle);
+        // a wrapper cannot finish or re-stale a task with a heartbeat
+        for reported in [Done, Cancelled, Stale] {
+            assert_eq!(resumed_state(Some(reported), Some(Pending)), Pending, "{reported}");
+            assert_eq!(resumed_state(Some(reported), None), Idle, "{reported}");
+        }
+    }
+}
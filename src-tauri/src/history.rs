@@ -0,0 +1,31 @@
+use log::error;
+use crate::store;
+use crate::types::{StateTransition, Task, TaskState};
+
+/// Build the transition record for a task that has just moved into its current state
+pub fn transition_for(task: &Task, from: Option<TaskState>, source: Option<String>) -> StateTransition {
+    StateTransition {
+        task_id: task.id.clone(),
+        project_id: task.project_id.clone(),
+        agent: task.agent.clone(),
+        from,
+        to: task.state,
+        timestamp: task.updated_at,
+        source,
+        detection_method: task.detection_method.clone(),
+        details: task.details.clone(),
+    }
+}
+
+/// Persist a transition. Call after releasing `APP_STATE` (the DB lock is taken first).
+pub fn record_transition(transition: &StateTransition) {
+    crate::metrics::observe_transition(transition);
+    if let Err(e) = store::with_connection(|conn| store::insert_transition(conn, transition)) {
+        error!("Failed to record transition for task {}: {e}", transition.task_id);
+    }
+}
+
+/// Recorded transitions for a task, oldest first
+pub fn task_history(task_id: &str) -> Result<Vec<StateTransition>, String> {
+    store::with_connection(|conn| store::load_task_transitions(conn, task_id))
+}
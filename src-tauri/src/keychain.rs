@@ -0,0 +1,64 @@
+use std::{io::Write, process::{Command, Output, Stdio}};
+
+/// Service the state key is stored under, e.g. `secret-tool lookup service tallr-state`
+const SERVICE: &str = "tallr-state";
+/// Label shown in the Secret Service on Linux
+const LABEL: &str = "Tallr state key";
+
+/// Run a secret store tool, passing `input` on stdin so secrets never show up in the process list
+fn run(program: &str, args: &[&str], input: Option<&str>) -> Result<Output, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {program}: {e}"))?;
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        stdin.write_all(input.as_bytes()).map_err(|e| format!("Failed to write to {program}: {e}"))?;
+    }
+    child.wait_with_output().map_err(|e| format!("Failed to run {program}: {e}"))
+}
+
+/// The secret stored in the OS secret store (macOS Keychain or the Secret Service on Linux), if any
+pub fn load() -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        run("security", &["find-generic-password", "-s", SERVICE, "-w"], None)
+    } else if cfg!(target_os = "linux") {
+        run("secret-tool", &["lookup", "service", SERVICE], None)
+    } else {
+        return None;
+    };
+    let output = output.ok().filter(|output| output.status.success())?;
+    let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!secret.is_empty()).then_some(secret)
+}
+
+/// Save the secret in the OS secret store, replacing any earlier one
+pub fn store(secret: &str) -> Result<(), String> {
+    let output = if cfg!(target_os = "macos") {
+        let command = format!("add-generic-password -U -s {SERVICE} -a tallr -w {secret}\n");
+        run("security", &["-i"], Some(&command))?
+    } else if cfg!(target_os = "linux") {
+        run("secret-tool", &["store", "--label", LABEL, "service", SERVICE], Some(secret))?
+    } else {
+        return Err("No OS secret store is supported on this platform".to_string());
+    };
+    // `security -i` reports success even when a command fails, so read the secret back
+    if !output.status.success() || load().as_deref() != Some(secret) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to save the secret in the OS secret store: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+/// Remove the secret from the OS secret store; nothing happens when there is none
+pub fn delete() {
+    let _ = if cfg!(target_os = "macos") {
+        run("security", &["delete-generic-password", "-s", SERVICE], None)
+    } else if cfg!(target_os = "linux") {
+        run("secret-tool", &["clear", "service", SERVICE], None)
+    } else {
+        return;
+    };
+}
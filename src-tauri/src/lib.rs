@@ -1,16 +1,41 @@
+mod analytics;
+mod archive;
+mod audit;
+pub mod api;
 mod auth;
+pub mod client;
 mod commands;
 mod constants;
+mod encryption;
+mod escalation;
+pub mod desktop;
+mod events;
 mod handlers;
+mod headless;
+mod heartbeat;
+mod history;
+mod keychain;
+mod metrics;
+mod notifications;
+mod redaction;
+mod reports;
+mod retention;
+mod server;
+mod services;
+mod settings;
 mod state;
+mod store;
+mod tokens;
 mod toolbar;
+mod transitions;
 mod tray;
-mod types;
+pub mod types;
 mod utils;
+mod webhooks;
 
 use commands::*;
-use handlers::*;
-use log::{error, info, warn};
+pub use headless::run_headless;
+use log::{info, warn};
 use state::initialize_app_state;
 use tauri::Manager;
 use toolbar::{setup_unified_toolbar, toolbar_action};
@@ -19,53 +44,14 @@ use utils::*;
 
 // HTTP server function
 async fn start_http_server(app_handle: tauri::AppHandle) {
-    use axum::Router;
-    use tokio::net::TcpListener;
-
-    // No CORS configuration necessary: only non-browser clients (Node CLI) call this server.
-    // let cors = CorsLayer::new()
-    //     .allow_origin(tower_http::cors::Any)
-    //     .allow_methods([axum::http::Method::GET, axum::http::Method::POST])
-    //     .allow_headers([
-    //         axum::http::header::CONTENT_TYPE,
-    //         axum::http::header::AUTHORIZATION,
-    //     ]);
-
-    let app = Router::new()
-        .route("/v1/state", axum::routing::get(get_state))
-        .route("/v1/tasks/upsert", axum::routing::post(upsert_task))
-        .route("/v1/tasks/state", axum::routing::post(update_task_state))
-        .route(
-            "/v1/tasks/details",
-            axum::routing::post(update_task_details),
-        )
-        .route("/v1/tasks/done", axum::routing::post(mark_task_done))
-        .route("/v1/tasks/delete", axum::routing::post(delete_task))
-        .route("/v1/tasks/pin", axum::routing::post(pin_task))
-        .route("/v1/setup/status", axum::routing::get(get_setup_status))
-        .route("/v1/health", axum::routing::get(health_check))
-        .route("/v1/debug/patterns", axum::routing::get(get_debug_patterns))
-        .route(
-            "/v1/debug/patterns/{task_id}",
-            axum::routing::get(get_debug_patterns_for_task),
-        )
-        .route("/v1/debug/update", axum::routing::post(update_debug_data))
-        .with_state(app_handle);
-
-    let listener = match TcpListener::bind("127.0.0.1:4317").await {
-        Ok(listener) => {
-            info!("HTTP server starting on 127.0.0.1:4317");
-            listener
-        }
-        Err(e) => {
-            error!("Failed to bind HTTP server: {e}");
-            return;
-        }
-    };
-
-    if let Err(e) = axum::serve(listener, app).await {
-        error!("HTTP server error: {e}");
-    }
+    // Read settings before the handle moves into the API context
+    let settings = settings::read_settings(&app_handle).unwrap_or_else(|e| {
+        warn!("Failed to load settings for HTTP server, using defaults: {e}");
+        Default::default()
+    });
+    let ctx = api::ApiContext::new(state::APP_STATE.clone(), std::sync::Arc::new(app_handle));
+    services::start(&settings, &ctx);
+    services::serve(&settings, ctx).await;
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -115,6 +101,7 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             open_ide_and_terminal,
             get_tasks,
+            get_state_snapshot,
             install_cli_globally,
             check_cli_permissions,
             get_setup_status_cmd,
@@ -128,8 +115,21 @@ pub fn run() {
             frontend_update_task_state,
             frontend_mark_task_done,
             frontend_delete_task,
+            frontend_archive_task,
+            frontend_restore_task,
+            search_archive_cmd,
             frontend_toggle_task_pin,
+            frontend_snooze_task,
             frontend_get_debug_data,
+            get_task_history_cmd,
+            list_api_tokens_cmd,
+            create_api_token_cmd,
+            rotate_api_token_cmd,
+            revoke_api_token_cmd,
+            get_auth_audit_cmd,
+            get_stats_cmd,
+            get_report_cmd,
+            get_report_markdown_cmd,
             toolbar_action
         ])
         .run(tauri::generate_context!())
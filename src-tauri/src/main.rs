@@ -2,5 +2,13 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
-    tallr_lib::run()
+    // `--headless` (or TALLR_HEADLESS=1) runs the tracking core without tray or window
+    let headless = std::env::args().skip(1).any(|arg| arg == "--headless")
+        || std::env::var("TALLR_HEADLESS").is_ok_and(|value| value == "1");
+
+    if headless {
+        tallr_lib::run_headless()
+    } else {
+        tallr_lib::run()
+    }
 }
@@ -0,0 +1,168 @@
+use std::{collections::{BTreeMap, HashMap}, fmt::Write, sync::Arc};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use crate::types::{AppState, StateTransition, TaskState};
+
+/// Upper bounds (seconds) of the PENDING duration histogram buckets
+const PENDING_BUCKETS: &[f64] = &[5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+
+const ALL_STATES: [TaskState; 7] = [
+    TaskState::Idle,
+    TaskState::Working,
+    TaskState::Pending,
+    TaskState::Error,
+    TaskState::Done,
+    TaskState::Cancelled,
+    TaskState::Stale,
+];
+
+#[derive(Default)]
+struct Counters {
+    transitions: BTreeMap<(String, TaskState), u64>,
+    notifications: BTreeMap<TaskState, u64>,
+    pending_since: HashMap<String, i64>,
+    pending_bucket_counts: Vec<u64>,
+    pending_count: u64,
+    pending_sum: f64,
+}
+
+// Process-lifetime counters; Prometheus handles the reset on restart
+static COUNTERS: Lazy<Arc<Mutex<Counters>>> = Lazy::new(|| {
+    Arc::new(Mutex::new(Counters {
+        pending_bucket_counts: vec![0; PENDING_BUCKETS.len()],
+        ..Default::default()
+    }))
+});
+
+/// Whether to serve `/metrics`: set `TALLR_METRICS=1` or configure a metrics token
+pub fn metrics_enabled() -> bool {
+    let flag = std::env::var("TALLR_METRICS").unwrap_or_default();
+    matches!(flag.to_ascii_lowercase().as_str(), "1" | "true" | "on")
+        || crate::auth::get_metrics_token().is_some()
+}
+
+/// Count a transition and, when a task leaves PENDING, observe how long it waited
+pub fn observe_transition(transition: &StateTransition) {
+    let mut counters = COUNTERS.lock();
+    let from = transition.from.map(|s| s.to_string()).unwrap_or_else(|| "NONE".to_string());
+    *counters.transitions.entry((from, transition.to)).or_default() += 1;
+
+    if let Some(entered) = counters.pending_since.remove(&transition.task_id) {
+        let waited = (transition.timestamp - entered).max(0) as f64;
+        for (index, bound) in PENDING_BUCKETS.iter().enumerate() {
+            if waited <= *bound {
+                counters.pending_bucket_counts[index] += 1;
+            }
+        }
+        counters.pending_count += 1;
+        counters.pending_sum += waited;
+    }
+
+    if transition.to == TaskState::Pending {
+        counters.pending_since.insert(transition.task_id.clone(), transition.timestamp);
+    }
+}
+
+/// Count a notification emitted for a task entering `state`
+pub fn observe_notification(state: TaskState) {
+    *COUNTERS.lock().notifications.entry(state).or_default() += 1;
+}
+
+/// Stop waiting for a deleted task to leave PENDING, so its entry does not linger forever
+pub fn forget_task(task_id: &str) {
+    COUNTERS.lock().pending_since.remove(task_id);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Render all metrics in the Prometheus text exposition format
+pub fn render(state: &Mutex<AppState>) -> String {
+    let mut out = String::new();
+
+    // Gauges from the live task list
+    let mut task_counts: BTreeMap<(TaskState, String, String), u64> = BTreeMap::new();
+    let aggregate = {
+        let state = state.lock();
+        for task in state.tasks.values() {
+            let project = state.projects.get(&task.project_id)
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| task.project_id.clone());
+            *task_counts.entry((task.state, task.agent.clone(), project)).or_default() += 1;
+        }
+        TaskState::aggregate(state.tasks.values().map(|t| t.state))
+    };
+
+    let _ = writeln!(out, "# HELP tallr_tasks Tasks on the dashboard by state, agent and project.");
+    let _ = writeln!(out, "# TYPE tallr_tasks gauge");
+    for ((state, agent, project), count) in &task_counts {
+        let _ = writeln!(
+            out,
+            "tallr_tasks{{state=\"{state}\",agent=\"{}\",project=\"{}\"}} {count}",
+            escape_label(agent),
+            escape_label(project)
+        );
+    }
+
+    let _ = writeln!(out, "# HELP tallr_aggregate_state Headline state shown in the tray (1 for the current state).");
+    let _ = writeln!(out, "# TYPE tallr_aggregate_state gauge");
+    for state in ALL_STATES.iter().filter(|s| !s.is_terminal()) {
+        let _ = writeln!(out, "tallr_aggregate_state{{state=\"{state}\"}} {}", u8::from(*state == aggregate));
+    }
+
+    let counters = COUNTERS.lock();
+
+    let _ = writeln!(out, "# HELP tallr_state_transitions_total State transitions recorded since startup.");
+    let _ = writeln!(out, "# TYPE tallr_state_transitions_total counter");
+    for ((from, to), count) in &counters.transitions {
+        let _ = writeln!(out, "tallr_state_transitions_total{{from=\"{from}\",to=\"{to}\"}} {count}");
+    }
+
+    let _ = writeln!(out, "# HELP tallr_notifications_total Notifications emitted since startup by task state.");
+    let _ = writeln!(out, "# TYPE tallr_notifications_total counter");
+    for (state, count) in &counters.notifications {
+        let _ = writeln!(out, "tallr_notifications_total{{state=\"{state}\"}} {count}");
+    }
+
+    let _ = writeln!(out, "# HELP tallr_pending_duration_seconds Time tasks spent waiting in PENDING.");
+    let _ = writeln!(out, "# TYPE tallr_pending_duration_seconds histogram");
+    for (bound, count) in PENDING_BUCKETS.iter().zip(&counters.pending_bucket_counts) {
+        let _ = writeln!(out, "tallr_pending_duration_seconds_bucket{{le=\"{bound}\"}} {count}");
+    }
+    let _ = writeln!(out, "tallr_pending_duration_seconds_bucket{{le=\"+Inf\"}} {}", counters.pending_count);
+    let _ = writeln!(out, "tallr_pending_duration_seconds_sum {}", counters.pending_sum);
+    let _ = writeln!(out, "tallr_pending_duration_seconds_count {}", counters.pending_count);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(task_id: &str, to: TaskState, timestamp: i64) -> StateTransition {
+        StateTransition {
+            task_id: task_id.to_string(),
+            project_id: "metrics".to_string(),
+            agent: "claude".to_string(),
+            from: None,
+            to,
+            timestamp,
+            source: None,
+            detection_method: None,
+            details: None,
+        }
+    }
+
+    #[test]
+    fn deleted_pending_tasks_stop_being_tracked() {
+        observe_transition(&transition("kept-pending", TaskState::Pending, 100));
+        observe_transition(&transition("deleted-pending", TaskState::Pending, 100));
+        forget_task("deleted-pending");
+
+        let counters = COUNTERS.lock();
+        assert_eq!(counters.pending_since.get("kept-pending"), Some(&100));
+        assert!(!counters.pending_since.contains_key("deleted-pending"));
+    }
+}
@@ -0,0 +1,436 @@
+use std::{collections::{BTreeSet, HashMap}, time::Duration};
+use chrono::{Local, NaiveTime};
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use regex::Regex;
+use crate::api::ApiContext;
+use crate::events::EventSink;
+use crate::types::{AppSettings, NotificationAction, NotificationRule, Project, QuietHours, Task, TaskState};
+use crate::utils::{current_timestamp, glob_regex};
+
+/// Window in which a repeat of the same notification for a task is collapsed, unless configured
+const DEFAULT_COOLDOWN_SECONDS: u64 = 60;
+
+/// A rule with its patterns compiled
+struct CompiledRule {
+    rule: NotificationRule,
+    project: Option<Regex>,
+    details: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn compile(rule: &NotificationRule) -> Result<Self, String> {
+        let label = rule.name.as_deref().unwrap_or("unnamed");
+        let project = rule.project.as_deref()
+            .map(glob_regex)
+            .transpose()
+            .map_err(|e| format!("Invalid project pattern in rule '{label}': {e}"))?;
+        let details = rule.details_pattern.as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("Invalid details pattern in rule '{label}': {e}"))?;
+
+        Ok(Self { rule: rule.clone(), project, details })
+    }
+
+    /// Whether every condition except the time in state matches
+    fn matches_task(&self, task: &Task, project: Option<&Project>) -> bool {
+        let rule = &self.rule;
+        if !rule.enabled || (!rule.states.is_empty() && !rule.states.contains(&task.state)) {
+            return false;
+        }
+        if let Some(pattern) = &self.project {
+            let matched = project.is_some_and(|p| pattern.is_match(&p.name) || pattern.is_match(&p.repo_path));
+            if !matched {
+                return false;
+            }
+        }
+        if rule.agent.as_ref().is_some_and(|agent| !agent.eq_ignore_ascii_case(&task.agent)) {
+            return false;
+        }
+        if let Some(method) = &rule.detection_method {
+            if task.detection_method.as_deref() != Some(method.as_str()) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.details {
+            if !pattern.is_match(task.details.as_deref().unwrap_or_default()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn min_seconds(&self) -> u64 {
+        self.rule.min_seconds_in_state.unwrap_or(0)
+    }
+}
+
+/// Quiet hours with parsed times
+struct QuietWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+    allow_escalations: bool,
+}
+
+impl QuietWindow {
+    fn parse(quiet_hours: &QuietHours) -> Result<Self, String> {
+        let parse_time = |time: &str| NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|e| format!("Invalid quiet hours time '{time}' (expected HH:MM): {e}"));
+        Ok(Self {
+            start: parse_time(&quiet_hours.start)?,
+            end: parse_time(&quiet_hours.end)?,
+            allow_escalations: quiet_hours.allow_escalations,
+        })
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            // Spans midnight, e.g. 22:00 - 07:00
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Notification settings in the form the engine uses
+struct Policy {
+    rules: Vec<CompiledRule>,
+    cooldown_seconds: u64,
+    quiet_hours: Option<QuietWindow>,
+}
+
+impl Policy {
+    fn from_settings(settings: &AppSettings) -> Result<Self, String> {
+        Ok(Self {
+            rules: settings.notification_rules.iter()
+                .map(CompiledRule::compile)
+                .collect::<Result<Vec<_>, _>>()?,
+            cooldown_seconds: settings.notification_cooldown_seconds.unwrap_or(DEFAULT_COOLDOWN_SECONDS),
+            quiet_hours: settings.quiet_hours.as_ref().map(QuietWindow::parse).transpose()?,
+        })
+    }
+}
+
+static POLICY: Lazy<RwLock<Policy>> = Lazy::new(|| RwLock::new(Policy {
+    rules: Vec::new(),
+    cooldown_seconds: DEFAULT_COOLDOWN_SECONDS,
+    quiet_hours: None,
+}));
+
+/// Per-task bookkeeping; lives in memory only
+#[derive(Default)]
+struct TaskNotifications {
+    /// Bumped whenever the task changes state, so delayed checks can tell it has moved on
+    generation: u64,
+    /// State, action and time of the last notification sent
+    last_sent: Option<(TaskState, NotificationAction, i64)>,
+    snoozed_until: Option<i64>,
+}
+
+static TASKS: Lazy<Mutex<HashMap<String, TaskNotifications>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Check that the notification settings are usable
+pub fn validate(settings: &AppSettings) -> Result<(), String> {
+    Policy::from_settings(settings).map(|_| ())
+}
+
+/// Load the notification settings. Invalid settings leave the current ones in place.
+pub fn configure(settings: &AppSettings) -> Result<(), String> {
+    let policy = Policy::from_settings(settings)?;
+    info!("Loaded {} notification rule(s)", policy.rules.len());
+    *POLICY.write() = policy;
+    Ok(())
+}
+
+/// Silence a task's notifications for `minutes`, or lift the snooze with 0.
+/// Returns when the snooze ends.
+pub fn snooze_task(task_id: &str, minutes: u64) -> Option<i64> {
+    let mut tasks = TASKS.lock();
+    let entry = tasks.entry(task_id.to_string()).or_default();
+    entry.snoozed_until = (minutes > 0).then(|| current_timestamp() + minutes as i64 * 60);
+    info!("Task {task_id} snoozed until {:?}", entry.snoozed_until);
+    entry.snoozed_until
+}
+
+/// Drop the bookkeeping for a deleted task
+pub fn forget_task(task_id: &str) {
+    TASKS.lock().remove(task_id);
+}
+
+/// Action for a task that has been in its state for `elapsed` seconds: the first matching rule's,
+/// or the built-in default (notify on PENDING and ERROR) when nothing matches on entry.
+/// Also returns the matching rule's time threshold, 0 for the default.
+fn decide(rules: &[CompiledRule], task: &Task, project: Option<&Project>, elapsed: u64) -> Option<(NotificationAction, u64)> {
+    let matched = rules.iter()
+        .find(|rule| rule.matches_task(task, project) && rule.min_seconds() <= elapsed)
+        .map(|rule| (rule.rule.action, rule.min_seconds()));
+
+    matched.or_else(|| {
+        (elapsed == 0 && matches!(task.state, TaskState::Pending | TaskState::Error))
+            .then_some((NotificationAction::Notify, 0))
+    })
+}
+
+/// Why a notification the rules asked for is held back, if it is
+fn hold_back(policy: &Policy, task: &Task, action: NotificationAction, now: i64) -> Option<&'static str> {
+    let mut tasks = TASKS.lock();
+    let entry = tasks.entry(task.id.clone()).or_default();
+
+    if entry.snoozed_until.is_some_and(|until| until > now) {
+        return Some("task is snoozed");
+    }
+    if let Some(quiet_hours) = &policy.quiet_hours {
+        let escalation_allowed = quiet_hours.allow_escalations && action == NotificationAction::Escalate;
+        if quiet_hours.contains(Local::now().time()) && !escalation_allowed {
+            return Some("quiet hours");
+        }
+    }
+    // Flapping detection repeats the same state; only the first notification in the window goes out
+    if let Some((state, last_action, sent_at)) = entry.last_sent {
+        if state == task.state && last_action == action && now - sent_at < policy.cooldown_seconds as i64 {
+            return Some("repeat within cooldown");
+        }
+    }
+
+    entry.last_sent = Some((task.state, action, now));
+    None
+}
+
+/// Emit the notification for a decided action
+fn dispatch(policy: &Policy, events: &dyn EventSink, task: &Task, project: Option<&Project>, action: NotificationAction) {
+    let (sound, urgent) = match action {
+        NotificationAction::Suppress => {
+            debug!("Notification for task {} ({}) suppressed by rule", task.id, task.state);
+            return;
+        }
+        NotificationAction::Notify => (false, false),
+        NotificationAction::NotifyWithSound => (true, false),
+        NotificationAction::Escalate => (true, true),
+    };
+    if let Some(reason) = hold_back(policy, task, action, current_timestamp()) {
+        debug!("Notification for task {} ({}) held back: {reason}", task.id, task.state);
+        return;
+    }
+    emit(events, task, project, task.state.as_str(), sound, urgent);
+}
+
+fn emit(events: &dyn EventSink, task: &Task, project: Option<&Project>, body: &str, sound: bool, urgent: bool) {
+    let project_name = project.map(|p| p.name.as_str()).unwrap_or("Unknown");
+    let notification_data = serde_json::json!({
+        "title": format!("{} - {}", project_name, task.agent),
+        "body": body,
+        "taskId": task.id,
+        "sound": sound,
+        "urgent": urgent,
+    });
+    events.emit_event("show-notification", &notification_data);
+    crate::metrics::observe_notification(task.state);
+}
+
+/// Whether the task's notifications are snoozed at `now`
+pub fn is_snoozed(task_id: &str, now: i64) -> bool {
+    TASKS.lock().get(task_id)
+        .and_then(|entry| entry.snoozed_until)
+        .is_some_and(|until| until > now)
+}
+
+/// Whether the rules suppress notifications for a task that has been in its state `waited_seconds`
+pub fn is_suppressed(task: &Task, project: Option<&Project>, waited_seconds: i64) -> bool {
+    let policy = POLICY.read();
+    let decided = decide(&policy.rules, task, project, waited_seconds.max(0) as u64);
+    matches!(decided, Some((NotificationAction::Suppress, _)))
+}
+
+/// Send an escalation reminder for a task that has waited `waited_seconds`, unless held back
+pub fn notify_escalation(events: &dyn EventSink, task: &Task, project: Option<&Project>, waited_seconds: i64) {
+    let policy = POLICY.read();
+    if let Some(reason) = hold_back(&policy, task, NotificationAction::Escalate, current_timestamp()) {
+        debug!("Escalation for task {} held back: {reason}", task.id);
+        return;
+    }
+    let body = format!("Still {} after {} min", task.state, waited_seconds / 60);
+    emit(events, task, project, &body, true, true);
+}
+
+/// Run the notification rules for a task update. `previous` is the state before the update
+/// (None for a new task); rules with a minimum time in state are re-checked once it has passed.
+/// Called with the app state lock held, like the other event emitters.
+pub fn on_task_update(ctx: &ApiContext, task: &Task, project: Option<&Project>, previous: Option<TaskState>) {
+    let policy = POLICY.read();
+    if let Some((action, _)) = decide(&policy.rules, task, project, 0) {
+        dispatch(&policy, ctx.events.as_ref(), task, project, action);
+    }
+
+    // Repeated updates in the same state keep the timers already running
+    if previous == Some(task.state) {
+        return;
+    }
+    let generation = {
+        let mut tasks = TASKS.lock();
+        let entry = tasks.entry(task.id.clone()).or_default();
+        entry.generation += 1;
+        entry.generation
+    };
+
+    let thresholds: BTreeSet<u64> = policy.rules.iter()
+        .filter(|rule| rule.min_seconds() > 0 && rule.matches_task(task, project))
+        .map(CompiledRule::min_seconds)
+        .collect();
+    for threshold in thresholds {
+        let ctx = ctx.clone();
+        let task_id = task.id.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(threshold)).await;
+            check_after_delay(&ctx, &task_id, generation, threshold);
+        });
+    }
+}
+
+/// Fire a time-based rule if the task is still in the state it entered
+fn check_after_delay(ctx: &ApiContext, task_id: &str, generation: u64, threshold: u64) {
+    if TASKS.lock().get(task_id).map(|entry| entry.generation) != Some(generation) {
+        return;
+    }
+
+    let state = ctx.state.lock();
+    let Some(task) = state.tasks.get(task_id) else { return };
+    let project = state.projects.get(&task.project_id);
+
+    // Only the rule whose threshold this is fires now; earlier ones already had their turn
+    let policy = POLICY.read();
+    match decide(&policy.rules, task, project, threshold) {
+        Some((action, min_seconds)) if min_seconds == threshold => {
+            debug!("Task {task_id} has been {} for {threshold}s", task.state);
+            dispatch(&policy, ctx.events.as_ref(), task, project, action);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use NotificationAction::*;
+
+    fn rules(rules: serde_json::Value) -> Vec<CompiledRule> {
+        let rules: Vec<NotificationRule> = serde_json::from_value(rules).unwrap();
+        rules.iter().map(|rule| CompiledRule::compile(rule).unwrap()).collect()
+    }
+
+    fn task(state: TaskState) -> Task {
+        Task {
+            id: "task".to_string(),
+            project_id: "project".to_string(),
+            agent: "claude".to_string(),
+            title: "Refactor billing".to_string(),
+            state,
+            details: None,
+            created_at: 0,
+            updated_at: 0,
+            pinned: false,
+            detection_method: None,
+            last_heartbeat: None,
+        }
+    }
+
+    fn project(name: &str) -> Project {
+        Project {
+            id: "project".to_string(),
+            name: name.to_string(),
+            repo_path: format!("/src/{name}"),
+            preferred_ide: "cursor".to_string(),
+            github_url: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn without_rules_only_entering_pending_or_error_notifies() {
+        assert_eq!(decide(&[], &task(TaskState::Pending), None, 0), Some((Notify, 0)));
+        assert_eq!(decide(&[], &task(TaskState::Error), None, 0), Some((Notify, 0)));
+        assert_eq!(decide(&[], &task(TaskState::Working), None, 0), None);
+        assert_eq!(decide(&[], &task(TaskState::Pending), None, 30), None);
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let rules = rules(json!([
+            { "name": "disabled", "enabled": false, "action": "escalate" },
+            { "project": "acme-*", "states": ["PENDING"], "action": "suppress" },
+            { "agent": "CLAUDE", "action": "notify-with-sound" },
+        ]));
+        let acme = project("acme-web");
+        let other = project("tallr");
+        assert_eq!(decide(&rules, &task(TaskState::Pending), Some(&acme), 0), Some((Suppress, 0)));
+        assert_eq!(decide(&rules, &task(TaskState::Pending), Some(&other), 0), Some((NotifyWithSound, 0)));
+        assert_eq!(decide(&rules, &task(TaskState::Working), Some(&acme), 0), Some((NotifyWithSound, 0)));
+    }
+
+    #[test]
+    fn rules_with_a_threshold_wait_for_it() {
+        let rules = rules(json!([
+            { "states": ["PENDING"], "minSecondsInState": 300, "action": "escalate" },
+            { "states": ["ERROR"], "minSecondsInState": 0, "action": "suppress" },
+        ]));
+        assert_eq!(decide(&rules, &task(TaskState::Pending), None, 0), Some((Notify, 0)), "default on entry");
+        assert_eq!(decide(&rules, &task(TaskState::Pending), None, 299), None);
+        assert_eq!(decide(&rules, &task(TaskState::Pending), None, 300), Some((Escalate, 300)));
+        // a threshold of 0 matches straight away, like no threshold
+        assert_eq!(decide(&rules, &task(TaskState::Error), None, 0), Some((Suppress, 0)));
+        assert_eq!(decide(&rules, &task(TaskState::Error), None, 45), Some((Suppress, 0)));
+    }
+
+    #[test]
+    fn details_and_detection_method_must_match_when_set() {
+        let rules = rules(json!([
+            { "detectionMethod": "hooks", "detailsPattern": "(?i)allow .*rm -rf", "action": "escalate" },
+        ]));
+        let mut risky = task(TaskState::Pending);
+        risky.details = Some("Allow Bash: rm -rf build/?".to_string());
+        assert_eq!(decide(&rules, &risky, None, 0), Some((Notify, 0)), "no detection method");
+        risky.detection_method = Some("hooks".to_string());
+        assert_eq!(decide(&rules, &risky, None, 0), Some((Escalate, 0)));
+        risky.details = Some("Allow edit?".to_string());
+        assert_eq!(decide(&rules, &risky, None, 0), Some((Notify, 0)));
+    }
+
+    fn quiet(start: &str, end: &str) -> QuietWindow {
+        let quiet_hours = QuietHours { start: start.to_string(), end: end.to_string(), allow_escalations: false };
+        QuietWindow::parse(&quiet_hours).unwrap()
+    }
+
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let lunch = quiet("12:00", "13:30");
+        assert!(!lunch.contains(at("11:59")));
+        assert!(lunch.contains(at("12:00")));
+        assert!(lunch.contains(at("13:29")));
+        assert!(!lunch.contains(at("13:30")), "the end is exclusive");
+    }
+
+    #[test]
+    fn quiet_hours_spanning_midnight() {
+        let night = quiet("22:00", "07:00");
+        for time in ["22:00", "23:59", "00:00", "03:15", "06:59"] {
+            assert!(night.contains(at(time)), "{time}");
+        }
+        for time in ["07:00", "12:00", "21:59"] {
+            assert!(!night.contains(at(time)), "{time}");
+        }
+    }
+
+    #[test]
+    fn quiet_hours_need_hh_mm_times() {
+        let quiet_hours = QuietHours { start: "10pm".to_string(), end: "07:00".to_string(), allow_escalations: false };
+        assert!(QuietWindow::parse(&quiet_hours).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use log::info;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use regex::Regex;
+use crate::types::{AppSettings, DebugData};
+
+/// What a secret is replaced with
+const MASK: &str = "[REDACTED]";
+
+/// Secrets agents commonly print, with the capture group holding the secret (0 masks the whole match)
+const BUILT_IN: [(&str, usize); 5] = [
+    // PEM private keys, including blocks cut off by the end of the buffer
+    (r"-----BEGIN[A-Z ]* PRIVATE KEY-----[\s\S]*?(?:-----END[A-Z ]* PRIVATE KEY-----|\z)", 0),
+    // AWS access key ids
+    (r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", 0),
+    // Authorization headers
+    (r"(?i)\bbearer\s+([A-Za-z0-9\-._~+/]{8,}=*)", 1),
+    // Provider keys recognisable by their prefix: OpenAI/Anthropic, GitHub, Slack, Google
+    (r"\b(?:sk-[A-Za-z0-9_\-]{20,}|gh[pousr]_[A-Za-z0-9]{30,}|github_pat_[A-Za-z0-9_]{30,}|xox[abposr]-[A-Za-z0-9\-]{10,}|AIza[0-9A-Za-z_\-]{35})", 0),
+    // `.env`, YAML and JSON assignments to anything named like a key, secret, token or password
+    (r#"(?i)[A-Z0-9_.\-]*(?:api[_\-]?key|secret|token|passw(?:or)?d|credentials?|private[_\-]?key)[A-Z0-9_.\-]*["']?\s*[:=]\s*["']?([^\s"',;]{6,})"#, 1),
+];
+
+/// A compiled pattern and the capture group it masks
+struct Rule {
+    pattern: Regex,
+    group: usize,
+}
+
+impl Rule {
+    /// Custom patterns mask their first capture group when they have one, otherwise the whole match
+    fn custom(pattern: &str) -> Result<Self, String> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| format!("Invalid redaction pattern '{pattern}': {e}"))?;
+        let group = usize::from(pattern.captures_len() > 1);
+        Ok(Self { pattern, group })
+    }
+
+    /// Mask every match in `text`, adding the number masked to `count`
+    fn apply(&self, text: &str, count: &mut usize) -> Option<String> {
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        let before = *count;
+        for captures in self.pattern.captures_iter(text) {
+            let Some(secret) = captures.get(self.group) else { continue };
+            // Skip empty matches and secrets an earlier rule already masked
+            if secret.is_empty() || secret.as_str().contains(MASK) {
+                continue;
+            }
+            redacted.push_str(&text[last..secret.start()]);
+            redacted.push_str(MASK);
+            last = secret.end();
+            *count += 1;
+        }
+        if *count == before {
+            return None;
+        }
+        redacted.push_str(&text[last..]);
+        Some(redacted)
+    }
+}
+
+static BUILT_IN_RULES: Lazy<Vec<Rule>> = Lazy::new(|| {
+    BUILT_IN.iter()
+        .map(|(pattern, group)| Rule { pattern: Regex::new(pattern).expect("built-in redaction pattern"), group: *group })
+        .collect()
+});
+
+// Redaction is on by default, with no custom patterns
+struct Policy {
+    enabled: bool,
+    custom: Vec<Rule>,
+}
+
+impl Policy {
+    fn from_settings(settings: &AppSettings) -> Result<Self, String> {
+        Ok(Self {
+            enabled: settings.redaction.enabled,
+            custom: settings.redaction.patterns.iter()
+                .map(|pattern| Rule::custom(pattern))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+static POLICY: Lazy<RwLock<Policy>> = Lazy::new(|| RwLock::new(Policy { enabled: true, custom: Vec::new() }));
+
+/// Check that the custom redaction patterns compile
+pub fn validate(settings: &AppSettings) -> Result<(), String> {
+    Policy::from_settings(settings).map(|_| ())
+}
+
+/// Load the redaction settings. Invalid settings leave the current ones in place.
+pub fn configure(settings: &AppSettings) -> Result<(), String> {
+    let policy = Policy::from_settings(settings)?;
+    info!(
+        "Secret redaction {} with {} custom pattern(s)",
+        if policy.enabled { "enabled" } else { "disabled" },
+        policy.custom.len()
+    );
+    *POLICY.write() = policy;
+    Ok(())
+}
+
+/// Mask secrets in `text`, returning how many were masked
+pub fn redact(text: &mut String) -> usize {
+    let policy = POLICY.read();
+    if !policy.enabled {
+        return 0;
+    }
+    let mut count = 0;
+    for rule in BUILT_IN_RULES.iter().chain(&policy.custom) {
+        if let Some(redacted) = rule.apply(text, &mut count) {
+            *text = redacted;
+        }
+    }
+    count
+}
+
+/// Mask secrets in task details reported by a wrapper
+pub fn redact_details(task_id: &str, details: &mut Option<String>) {
+    let count = details.as_mut().map(redact).unwrap_or_default();
+    if count > 0 {
+        info!("Masked {count} secret(s) in details of task {task_id}");
+    }
+}
+
+/// Mask secrets in a terminal buffer and its detection history, recording the count for the debug view
+pub fn redact_debug_data(debug_data: &mut DebugData) {
+    let mut count = redact(&mut debug_data.cleaned_buffer);
+    for entry in &mut debug_data.detection_history {
+        count += redact(&mut entry.details);
+    }
+    if count > 0 {
+        info!("Masked {count} secret(s) in debug output of task {}", debug_data.task_id);
+    }
+    debug_data.redactions = count;
+}
@@ -0,0 +1,237 @@
+use std::{cmp::Reverse, fmt::Write as _, fs, path::PathBuf, time::Duration};
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone, Weekday};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use crate::analytics::{compute_stats, load_intervals, median};
+use crate::types::{
+    AppSettings, AppState, DigestReport, DigestTask, DigestWait, ReportPeriod, ReportSchedule, StatsBucket, TaskState,
+};
+use crate::utils::{current_timestamp, get_app_data_dir};
+
+/// Longest waits listed in a digest
+const LONGEST_WAITS_LIMIT: usize = 5;
+/// How often the schedule checks whether a report is due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Report schedule with parsed time and weekday
+struct Schedule {
+    at: NaiveTime,
+    weekly_on: Option<Weekday>,
+}
+
+impl Schedule {
+    fn parse(schedule: &ReportSchedule) -> Result<Self, String> {
+        let at = NaiveTime::parse_from_str(&schedule.at, "%H:%M")
+            .map_err(|e| format!("Invalid report time '{}' (expected HH:MM): {e}", schedule.at))?;
+        let weekly_on = schedule.weekly_on.as_deref()
+            .map(|day| day.parse::<Weekday>().map_err(|_| format!("Invalid weekday for weekly report: {day}")))
+            .transpose()?;
+        Ok(Self { at, weekly_on })
+    }
+}
+
+static SCHEDULE: Lazy<RwLock<Option<Schedule>>> = Lazy::new(|| RwLock::new(None));
+
+/// Check that the report schedule is usable
+pub fn validate(settings: &AppSettings) -> Result<(), String> {
+    settings.report_schedule.as_ref().map(Schedule::parse).transpose().map(|_| ())
+}
+
+/// Load the report schedule. An invalid schedule leaves the current one in place.
+pub fn configure(settings: &AppSettings) -> Result<(), String> {
+    let schedule = settings.report_schedule.as_ref().map(Schedule::parse).transpose()?;
+    if let Some(schedule) = &schedule {
+        info!("Digest reports scheduled daily at {} (weekly on {:?})", schedule.at.format("%H:%M"), schedule.weekly_on);
+    }
+    *SCHEDULE.write() = schedule;
+    Ok(())
+}
+
+/// Digest of the `period` ending at `until` (default now), from the transition history and live tasks
+pub fn build_report(state: &Mutex<AppState>, period: ReportPeriod, until: Option<i64>) -> Result<DigestReport, String> {
+    let until = until.unwrap_or_else(current_timestamp);
+    let since = until - period.seconds();
+    let (intervals, project_names) = load_intervals(state, since, until)?;
+    let stats = compute_stats(&intervals, &project_names, since, until);
+
+    // Sessions belong to one project, so the project buckets add up
+    let mut totals = StatsBucket { key: "total".to_string(), label: "Total".to_string(), ..Default::default() };
+    for bucket in &stats.by_project {
+        totals.working_seconds += bucket.working_seconds;
+        totals.pending_seconds += bucket.pending_seconds;
+        totals.sessions += bucket.sessions;
+        totals.error_sessions += bucket.error_sessions;
+    }
+    if totals.sessions > 0 {
+        totals.error_rate = totals.error_sessions as f64 / totals.sessions as f64;
+    }
+    totals.median_response_seconds = median(intervals.iter()
+        .filter_map(|interval| match (interval.state, interval.left) {
+            (TaskState::Pending, Some((left_at, TaskState::Working))) if left_at >= since && left_at < until => {
+                Some(left_at - interval.entered_at)
+            }
+            _ => None,
+        })
+        .collect());
+
+    let project_name = |project_id: &str| project_names.get(project_id).cloned().unwrap_or_else(|| project_id.to_string());
+    let state = state.lock();
+
+    let mut longest_waits: Vec<DigestWait> = intervals.iter()
+        .filter(|interval| interval.state == TaskState::Pending && interval.end > interval.entered_at)
+        .map(|interval| DigestWait {
+            task_id: interval.task_id.clone(),
+            project: project_name(&interval.project_id),
+            agent: interval.agent.clone(),
+            title: state.tasks.get(&interval.task_id).map(|task| task.title.clone()),
+            started_at: interval.entered_at,
+            seconds: interval.end - interval.entered_at,
+            ongoing: interval.left.is_none(),
+        })
+        .collect();
+    longest_waits.sort_by_key(|wait| Reverse(wait.seconds));
+    longest_waits.truncate(LONGEST_WAITS_LIMIT);
+
+    let mut open_tasks: Vec<DigestTask> = state.tasks.values()
+        .filter(|task| !task.state.is_terminal())
+        .map(|task| DigestTask {
+            task_id: task.id.clone(),
+            project: state.projects.get(&task.project_id)
+                .map(|project| project.name.clone())
+                .unwrap_or_else(|| project_name(&task.project_id)),
+            agent: task.agent.clone(),
+            title: task.title.clone(),
+            state: task.state,
+            since: task.updated_at,
+        })
+        .collect();
+    open_tasks.sort_by_key(|task| task.since);
+
+    Ok(DigestReport {
+        period,
+        since,
+        until,
+        totals,
+        by_project: stats.by_project,
+        longest_waits,
+        open_tasks,
+    })
+}
+
+/// `1h 20m`, `12m` or `45s`
+fn format_duration(seconds: i64) -> String {
+    match seconds {
+        s if s >= 3600 => format!("{}h {}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+fn format_time(timestamp: i64, format: &str) -> String {
+    Local.timestamp_opt(timestamp, 0).single()
+        .map(|time| time.format(format).to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// The digest as a Markdown document
+pub fn render_markdown(report: &DigestReport) -> String {
+    let mut out = String::new();
+    let totals = &report.totals;
+    let _ = writeln!(out, "# Tallr {} digest\n", report.period.as_str());
+    let _ = writeln!(out, "_{} to {}_\n", format_time(report.since, "%Y-%m-%d %H:%M"), format_time(report.until, "%Y-%m-%d %H:%M"));
+    let _ = write!(out, "**{} session(s)** across {} project(s): {} working, {} waiting, {} with errors",
+                   totals.sessions, report.by_project.len(), format_duration(totals.working_seconds),
+                   format_duration(totals.pending_seconds), totals.error_sessions);
+    if let Some(median) = totals.median_response_seconds {
+        let _ = write!(out, ", median response {}", format_duration(median));
+    }
+    out.push_str(".\n\n## Projects\n\n");
+
+    if report.by_project.is_empty() {
+        out.push_str("_No activity._\n");
+    } else {
+        out.push_str("| Project | Sessions | Working | Waiting | Errors | Median response |\n");
+        out.push_str("|---|---:|---:|---:|---:|---:|\n");
+        for bucket in &report.by_project {
+            let _ = writeln!(out, "| {} | {} | {} | {} | {} | {} |",
+                             bucket.label, bucket.sessions, format_duration(bucket.working_seconds),
+                             format_duration(bucket.pending_seconds), bucket.error_sessions,
+                             bucket.median_response_seconds.map(format_duration).unwrap_or_else(|| "-".to_string()));
+        }
+    }
+
+    out.push_str("\n## Longest waits\n\n");
+    if report.longest_waits.is_empty() {
+        out.push_str("_None._\n");
+    }
+    for wait in &report.longest_waits {
+        let _ = writeln!(out, "- **{}** / {} - {}: {} from {}{}",
+                         wait.project, wait.agent, wait.title.as_deref().unwrap_or(&wait.task_id),
+                         format_duration(wait.seconds), format_time(wait.started_at, "%a %H:%M"),
+                         if wait.ongoing { " (still waiting)" } else { "" });
+    }
+
+    out.push_str("\n## Still open\n\n");
+    if report.open_tasks.is_empty() {
+        out.push_str("_None._\n");
+    }
+    for task in &report.open_tasks {
+        let _ = writeln!(out, "- **{}** / {} - {}: {} since {}",
+                         task.project, task.agent, task.title, task.state, format_time(task.since, "%a %H:%M"));
+    }
+    out
+}
+
+/// Write the scheduled reports due at `now` that are not on disk yet; returns the files written.
+/// Files are named by period and date, so a restart does not write the same report twice.
+fn write_due_reports(state: &Mutex<AppState>, now: DateTime<Local>) -> Result<Vec<PathBuf>, String> {
+    let periods = match SCHEDULE.read().as_ref() {
+        Some(schedule) if now.time() >= schedule.at => {
+            let mut periods = vec![ReportPeriod::Daily];
+            if schedule.weekly_on == Some(now.weekday()) {
+                periods.push(ReportPeriod::Weekly);
+            }
+            periods
+        }
+        _ => return Ok(Vec::new()),
+    };
+
+    let dir = get_app_data_dir()?.join("reports");
+    let mut written = Vec::new();
+    for period in periods {
+        let stem = format!("{}-{}", period.as_str(), now.format("%Y-%m-%d"));
+        let markdown_path = dir.join(format!("{stem}.md"));
+        if markdown_path.exists() {
+            continue;
+        }
+
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create reports directory: {e}"))?;
+        let report = build_report(state, period, Some(now.timestamp()))?;
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize report: {e}"))?;
+        fs::write(dir.join(format!("{stem}.json")), json)
+            .map_err(|e| format!("Failed to write report: {e}"))?;
+        // Markdown last: its presence marks the report as written
+        fs::write(&markdown_path, render_markdown(&report))
+            .map_err(|e| format!("Failed to write report: {e}"))?;
+        written.push(markdown_path);
+    }
+    Ok(written)
+}
+
+/// Background writer for scheduled reports; runs for the life of the HTTP server
+pub async fn run_schedule(state: crate::state::SharedState) {
+    let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        match write_due_reports(&state, Local::now()) {
+            Ok(written) => {
+                for path in written {
+                    info!("Wrote digest report {path:?}");
+                }
+            }
+            Err(e) => warn!("Failed to write scheduled report: {e}"),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{collections::HashMap, time::Duration};
+use log::{error, info};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use rusqlite::Connection;
+use crate::api::ApiContext;
+use crate::events::emit_task_event;
+use crate::state::persist_state;
+use crate::store;
+use crate::types::{AppSettings, DebugData, RetentionSettings, Task, TaskEvent};
+use crate::utils::current_timestamp;
+
+/// How often the retention rules run
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// How long finished tasks stay when `keep_done_hours` is not set
+const DEFAULT_KEEP_DONE_SECONDS: i64 = 30;
+
+static SETTINGS: Lazy<RwLock<RetentionSettings>> = Lazy::new(Default::default);
+
+/// What one sweep removed
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SweepOutcome {
+    pub removed_tasks: usize,
+    pub archived_tasks: usize,
+    pub removed_debug: usize,
+    pub trimmed_debug: usize,
+}
+
+/// Load the retention rules
+pub fn configure(settings: &AppSettings) {
+    *SETTINGS.write() = settings.retention.clone();
+}
+
+/// Drop the oldest output from a debug buffer over the configured cap. Returns whether it was trimmed.
+pub fn trim_debug_data(debug_data: &mut DebugData) -> bool {
+    let max_bytes = SETTINGS.read().max_debug_bytes;
+    let buffer = &mut debug_data.cleaned_buffer;
+    if buffer.len() <= max_bytes {
+        return false;
+    }
+    let mut start = buffer.len() - max_bytes;
+    while !buffer.is_char_boundary(start) {
+        start += 1;
+    }
+    buffer.drain(..start);
+    true
+}
+
+/// Finished, unpinned tasks that have outlived the rules
+fn expired_tasks<'a>(settings: &RetentionSettings, tasks: impl Iterator<Item = &'a Task>, now: i64) -> Vec<String> {
+    let keep_seconds = settings.keep_done_hours
+        .map(|hours| hours as i64 * 60 * 60)
+        .unwrap_or(DEFAULT_KEEP_DONE_SECONDS);
+
+    let mut finished_by_project: HashMap<&str, Vec<&Task>> = HashMap::new();
+    for task in tasks.filter(|task| task.state.is_terminal() && !task.pinned) {
+        finished_by_project.entry(task.project_id.as_str()).or_default().push(task);
+    }
+
+    let mut expired = Vec::new();
+    for mut finished in finished_by_project.into_values() {
+        finished.sort_by_key(|task| std::cmp::Reverse(task.updated_at));
+        for (index, task) in finished.into_iter().enumerate() {
+            let too_old = now - task.updated_at > keep_seconds;
+            let over_limit = settings.keep_last_per_project.is_some_and(|limit| index >= limit);
+            if too_old || over_limit {
+                expired.push(task.id.clone());
+            }
+        }
+    }
+    expired
+}
+
+/// Apply the retention rules once: remove (or archive) expired tasks, drop debug data
+/// without a task and trim oversized debug buffers
+pub fn sweep(ctx: &ApiContext, now: i64) -> SweepOutcome {
+    let settings = SETTINGS.read().clone();
+    let outcome = if settings.archive {
+        store::with_connection(|conn| Ok(sweep_state(ctx, &settings, Some(conn), now))).unwrap_or_else(|e| {
+            error!("Failed to archive expired tasks: {e}");
+            SweepOutcome::default()
+        })
+    } else {
+        sweep_state(ctx, &settings, None, now)
+    };
+    if outcome == SweepOutcome::default() {
+        return outcome;
+    }
+
+    if let Err(e) = persist_state(&ctx.state) {
+        error!("Failed to save app state after retention sweep: {e}");
+    }
+    info!("Retention sweep: {outcome:?}");
+    outcome
+}
+
+/// Apply the rules to the app state, archiving expired tasks to `archive_to` when given
+fn sweep_state(ctx: &ApiContext, settings: &RetentionSettings, archive_to: Option<&mut Connection>, now: i64) -> SweepOutcome {
+    let mut outcome = SweepOutcome::default();
+    let mut state = ctx.state.lock();
+
+    let expired: Vec<Task> = expired_tasks(settings, state.tasks.values(), now).into_iter()
+        .filter_map(|task_id| state.tasks.get(&task_id).cloned())
+        .collect();
+    // Write the archive rows first, so a failure leaves the tasks on the dashboard
+    let expired = match archive_to {
+        Some(conn) if !expired.is_empty() => match store::archive_tasks(conn, &expired, now) {
+            Ok(()) => {
+                outcome.archived_tasks = expired.len();
+                expired
+            }
+            Err(e) => {
+                error!("Failed to archive expired tasks: {e}");
+                Vec::new()
+            }
+        },
+        _ => expired,
+    };
+    for task in &expired {
+        state.tasks.remove(&task.id);
+        crate::notifications::forget_task(&task.id);
+        crate::metrics::forget_task(&task.id);
+        let revision = state.next_revision();
+        emit_task_event(ctx.events.as_ref(), TaskEvent::TaskDeleted { revision, task_id: task.id.clone() });
+    }
+    outcome.removed_tasks = expired.len();
+
+    let mut debug_data = std::mem::take(&mut state.debug_data);
+    let before = debug_data.len();
+    debug_data.retain(|task_id, _| state.tasks.contains_key(task_id));
+    outcome.removed_debug = before - debug_data.len();
+    outcome.trimmed_debug = debug_data.values_mut().map(trim_debug_data).filter(|trimmed| *trimmed).count();
+    state.debug_data = debug_data;
+
+    if outcome != SweepOutcome::default() {
+        state.updated_at = now;
+    }
+    drop(state);
+    if outcome.removed_tasks > 0 {
+        ctx.events.state_changed();
+    }
+    outcome
+}
+
+/// Background sweeper; the first sweep runs straight away, so startup cleanup follows the same rules
+pub async fn run(ctx: ApiContext) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        sweep(&ctx, current_timestamp());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TaskState;
+
+    fn task(id: &str, project_id: &str, state: TaskState, updated_at: i64) -> Task {
+        Task {
+            id: id.to_string(),
+            project_id: project_id.to_string(),
+            agent: "claude".to_string(),
+            title: id.to_string(),
+            state,
+            details: None,
+            created_at: 0,
+            updated_at,
+            pinned: false,
+            detection_method: None,
+            last_heartbeat: None,
+        }
+    }
+
+    fn expired(settings: &RetentionSettings, tasks: &[Task], now: i64) -> Vec<String> {
+        let mut expired = expired_tasks(settings, tasks.iter(), now);
+        expired.sort();
+        expired
+    }
+
+    #[test]
+    fn finished_tasks_expire_after_30_seconds_by_default() {
+        let tasks = [
+            task("fresh", "p", TaskState::Done, 1000),
+            task("old", "p", TaskState::Cancelled, 969),
+            task("open", "p", TaskState::Pending, 0),
+        ];
+        let settings = RetentionSettings::default();
+        assert_eq!(expired(&settings, &tasks, 999), Vec::<String>::new());
+        assert_eq!(expired(&settings, &tasks, 1030), ["old"]);
+        assert_eq!(expired(&settings, &tasks, 1031), ["fresh", "old"]);
+    }
+
+    #[test]
+    fn only_the_newest_finished_tasks_per_project_are_kept() {
+        let settings = RetentionSettings { keep_done_hours: Some(24), keep_last_per_project: Some(1), ..Default::default() };
+        let tasks = [
+            task("a-new", "a", TaskState::Done, 300),
+            task("a-old", "a", TaskState::Done, 200),
+            task("a-open", "a", TaskState::Working, 100),
+            task("b-only", "b", TaskState::Done, 100),
+        ];
+        assert_eq!(expired(&settings, &tasks, 400), ["a-old"]);
+        // the age limit still applies to the ones kept
+        assert_eq!(expired(&settings, &tasks, 100 + 24 * 60 * 60 + 1), ["a-old", "b-only"]);
+    }
+
+    #[test]
+    fn pinned_tasks_never_expire() {
+        let settings = RetentionSettings { keep_last_per_project: Some(0), ..Default::default() };
+        let mut pinned = task("pinned", "p", TaskState::Done, 0);
+        pinned.pinned = true;
+        assert_eq!(expired(&settings, &[pinned, task("unpinned", "p", TaskState::Done, 0)], 10_000), ["unpinned"]);
+    }
+}
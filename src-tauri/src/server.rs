@@ -0,0 +1,220 @@
+use std::{fs, net::SocketAddr, path::PathBuf};
+use axum::Router;
+use log::{error, info, warn};
+use serde::Serialize;
+use tokio::net::TcpListener;
+use crate::types::AppSettings;
+use crate::utils::{current_timestamp, get_app_data_dir};
+
+#[cfg(unix)]
+use std::{os::unix::fs::{DirBuilderExt, PermissionsExt}, path::Path};
+
+/// Listen address used when neither settings nor environment choose one
+pub const DEFAULT_SERVER_ADDRESS: &str = "127.0.0.1:4317";
+
+/// Where the HTTP API should listen
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Preferred TCP address as `host:port`
+    pub address: String,
+    /// Optional Unix domain socket served alongside TCP
+    pub unix_socket: Option<PathBuf>,
+}
+
+impl ServerConfig {
+    /// Resolve the listen configuration. `TALLR_ADDR`, `TALLR_PORT` and `TALLR_SOCKET`
+    /// take precedence over settings, which take precedence over the defaults.
+    pub fn resolve(settings: &AppSettings) -> Self {
+        let mut address = std::env::var("TALLR_ADDR")
+            .ok()
+            .or_else(|| settings.server_address.clone())
+            .filter(|address| !address.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_SERVER_ADDRESS.to_string());
+
+        if let Some(port) = std::env::var("TALLR_PORT").ok().and_then(|p| p.parse::<u16>().ok()) {
+            address = format!("{}:{port}", host_of(&address));
+        }
+
+        let unix_socket = std::env::var("TALLR_SOCKET")
+            .ok()
+            .or_else(|| settings.unix_socket_path.clone())
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+
+        Self { address, unix_socket }
+    }
+}
+
+/// Host part of a `host:port` address
+fn host_of(address: &str) -> &str {
+    address.rsplit_once(':').map(|(host, _)| host).unwrap_or(address)
+}
+
+/// The endpoint the running app is reachable on, written for local clients to discover
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerDiscovery {
+    url: Option<String>,
+    address: Option<String>,
+    port: Option<u16>,
+    socket: Option<String>,
+    pid: u32,
+    started_at: i64,
+}
+
+/// Get path to the discovery file (next to `auth.token`)
+pub fn get_discovery_file_path() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("server.json"))
+}
+
+/// Write the discovery file atomically so clients never read a partial file
+fn write_discovery_file(discovery: &ServerDiscovery) -> Result<(), String> {
+    let path = get_discovery_file_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+    }
+
+    let json = serde_json::to_string_pretty(discovery)
+        .map_err(|e| format!("Failed to serialize discovery file: {e}"))?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)
+        .map_err(|e| format!("Failed to write discovery file: {e}"))?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| format!("Failed to replace discovery file: {e}"))
+}
+
+/// Bind the preferred address, falling back to a free port on the same host
+async fn bind_tcp(address: &str) -> Option<TcpListener> {
+    match TcpListener::bind(address).await {
+        Ok(listener) => return Some(listener),
+        Err(e) => warn!("Failed to bind HTTP server to {address}: {e}; trying a free port"),
+    }
+
+    let fallback = format!("{}:0", host_of(address));
+    match TcpListener::bind(&fallback).await {
+        Ok(listener) => Some(listener),
+        Err(e) => {
+            error!("Failed to bind HTTP server to {fallback}: {e}");
+            None
+        }
+    }
+}
+
+/// Bind a Unix domain socket readable and writable only by the current user
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> Result<tokio::net::UnixListener, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create socket directory: {e}"))?;
+    }
+
+    if path.exists() {
+        // A socket that still accepts connections belongs to another running instance
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(format!("Socket {} is already in use", path.display()));
+        }
+        fs::remove_file(path)
+            .map_err(|e| format!("Failed to remove stale socket: {e}"))?;
+    }
+
+    // Bind inside a private (0700) directory and move the socket into place only once it is 0600,
+    // so other local users never get a window to connect
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let staging = parent.join(format!(".tallr-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&staging);
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .map_err(|e| format!("Failed to create socket directory: {e}"))?;
+    let staged = staging.join("s");
+    let listener = tokio::net::UnixListener::bind(&staged)
+        .map_err(|e| format!("Failed to bind socket {}: {e}", path.display()))
+        .and_then(|listener| {
+            fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))
+                .map_err(|e| format!("Failed to set socket permissions: {e}"))?;
+            fs::rename(&staged, path)
+                .map_err(|e| format!("Failed to move socket to {}: {e}", path.display()))?;
+            Ok(listener)
+        });
+    let _ = fs::remove_dir_all(&staging);
+    listener
+}
+
+/// Serve the router on TCP and, when configured, a Unix domain socket.
+/// The chosen endpoints are written to the discovery file once bound.
+pub async fn serve(app: Router, config: ServerConfig) {
+    let tcp_listener = bind_tcp(&config.address).await;
+    let tcp_address = tcp_listener.as_ref().and_then(|listener| listener.local_addr().ok());
+
+    #[cfg(unix)]
+    let unix_listener = config.unix_socket.as_deref().and_then(|path| match bind_unix(path) {
+        Ok(listener) => Some(listener),
+        Err(e) => {
+            error!("{e}");
+            None
+        }
+    });
+    #[cfg(unix)]
+    let socket_path = unix_listener.as_ref().and(config.unix_socket.clone());
+
+    #[cfg(not(unix))]
+    let socket_path: Option<PathBuf> = {
+        if config.unix_socket.is_some() {
+            warn!("Unix domain sockets are not supported on this platform; ignoring socket path");
+        }
+        None
+    };
+
+    if tcp_address.is_none() && socket_path.is_none() {
+        error!("HTTP server has no listener; local clients will not be able to connect");
+        return;
+    }
+
+    if let Some(address) = tcp_address {
+        info!("HTTP server starting on {address}");
+    }
+    if let Some(path) = &socket_path {
+        info!("HTTP server listening on unix socket {}", path.display());
+    }
+
+    let discovery = ServerDiscovery {
+        url: tcp_address.map(|address| format!("http://{address}")),
+        address: tcp_address.map(|address| address.ip().to_string()),
+        port: tcp_address.map(|address| address.port()),
+        socket: socket_path.map(|path| path.display().to_string()),
+        pid: std::process::id(),
+        started_at: current_timestamp(),
+    };
+    if let Err(e) = write_discovery_file(&discovery) {
+        warn!("{e}");
+    }
+
+    let tcp_server = {
+        let app = app.clone();
+        async move {
+            if let Some(listener) = tcp_listener {
+                // Peer addresses feed the auth audit log and rate limiter
+                let service = app.into_make_service_with_connect_info::<SocketAddr>();
+                if let Err(e) = axum::serve(listener, service).await {
+                    error!("HTTP server error: {e}");
+                }
+            }
+        }
+    };
+
+    #[cfg(unix)]
+    let unix_server = async move {
+        if let Some(listener) = unix_listener {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!("Unix socket server error: {e}");
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let unix_server = async move {
+        drop(app);
+    };
+
+    tokio::join!(tcp_server, unix_server);
+}
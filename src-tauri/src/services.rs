@@ -0,0 +1,40 @@
+use log::warn;
+use crate::api::{build_router, ApiContext};
+use crate::server::{self, ServerConfig};
+use crate::types::AppSettings;
+use crate::{escalation, heartbeat, notifications, redaction, reports, retention, webhooks};
+
+/// Apply the settings to every subsystem the API uses; a part that fails to load is logged and skipped
+pub fn configure(settings: &AppSettings) {
+    if let Err(e) = notifications::configure(settings) {
+        warn!("Failed to load notification rules: {e}");
+    }
+    if let Err(e) = webhooks::configure(settings) {
+        warn!("Failed to load webhooks: {e}");
+    }
+    if let Err(e) = redaction::configure(settings) {
+        warn!("Failed to load redaction patterns: {e}");
+    }
+    escalation::configure(settings);
+    heartbeat::configure(settings);
+    retention::configure(settings);
+    if let Err(e) = reports::configure(settings) {
+        warn!("Failed to load report schedule: {e}");
+    }
+}
+
+/// Apply the settings and start the background jobs: escalation, stale-session detection,
+/// retention and scheduled reports
+pub fn start(settings: &AppSettings, ctx: &ApiContext) {
+    configure(settings);
+    tokio::spawn(escalation::run(ctx.clone()));
+    tokio::spawn(heartbeat::run(ctx.clone()));
+    tokio::spawn(retention::run(ctx.clone()));
+    tokio::spawn(reports::run_schedule(ctx.state.clone()));
+}
+
+/// Serve the API on the address from settings (or the environment) until the server stops
+pub async fn serve(settings: &AppSettings, ctx: ApiContext) {
+    let config = ServerConfig::resolve(settings);
+    server::serve(build_router(ctx), config).await;
+}
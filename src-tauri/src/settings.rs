@@ -0,0 +1,69 @@
+use std::{fs, path::{Path, PathBuf}};
+use tauri::{AppHandle, Manager};
+use crate::types::AppSettings;
+use crate::utils::{get_app_config_dir, get_app_identifier_data_dir};
+
+/// Path of the settings file in Tauri's app config directory.
+/// This is the app data directory on macOS and Windows, and `$XDG_CONFIG_HOME` on Linux.
+fn settings_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_config_dir = app.path().app_config_dir()
+        .map_err(|e| format!("Failed to get app config dir: {e}"))?;
+    Ok(app_config_dir.join("settings.json"))
+}
+
+/// Settings written by versions that kept them in the app data directory
+fn legacy_settings_file_path(app: &AppHandle) -> Option<PathBuf> {
+    let legacy = app.path().app_data_dir().ok()?.join("settings.json");
+    legacy.exists().then_some(legacy)
+}
+
+/// Read settings from disk, falling back to defaults when no file exists yet
+pub fn read_settings(app: &AppHandle) -> Result<AppSettings, String> {
+    read_settings_file(&settings_file_path(app)?, legacy_settings_file_path(app))
+}
+
+/// Settings for headless mode, where there is no Tauri app to ask for its config directory:
+/// the file named by `TALLR_SETTINGS`, otherwise the one the desktop app uses
+pub fn read_headless_settings() -> Result<AppSettings, String> {
+    if let Some(path) = std::env::var_os("TALLR_SETTINGS").filter(|path| !path.is_empty()) {
+        return read_settings_file(&PathBuf::from(path), None);
+    }
+    let legacy = get_app_identifier_data_dir().ok()
+        .map(|dir| dir.join("settings.json"))
+        .filter(|legacy| legacy.exists());
+    read_settings_file(&get_app_config_dir()?.join("settings.json"), legacy)
+}
+
+fn read_settings_file(settings_file: &Path, legacy: Option<PathBuf>) -> Result<AppSettings, String> {
+    let mut settings_file = settings_file.to_path_buf();
+    
+    if !settings_file.exists() {
+        match legacy {
+            Some(legacy) => settings_file = legacy,
+            None => return Ok(AppSettings::default()),
+        }
+    }
+    
+    let settings_content = fs::read_to_string(&settings_file)
+        .map_err(|e| format!("Failed to read settings file: {e}"))?;
+    
+    serde_json::from_str(&settings_content)
+        .map_err(|e| format!("Failed to parse settings: {e}"))
+}
+
+/// Write settings to disk
+pub fn write_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let settings_file = settings_file_path(app)?;
+    
+    // Ensure directory exists
+    if let Some(parent) = settings_file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app config dir: {e}"))?;
+    }
+    
+    let settings_json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {e}"))?;
+    
+    fs::write(&settings_file, settings_json)
+        .map_err(|e| format!("Failed to write settings file: {e}"))
+}
@@ -1,34 +1,33 @@
 use std::{fs, sync::Arc};
 use parking_lot::Mutex;
 use once_cell::sync::Lazy;
-use log::{error, warn, debug};
-use crate::types::AppState;
-use crate::utils::{current_timestamp, get_sessions_file_path};
+use log::{warn, debug, info};
+use crate::encryption;
+use crate::store;
+use crate::types::{AppState, TaskState};
+use crate::utils::{get_database_path, get_sessions_file_path, restrict_permissions};
+
+/// App state shared between the HTTP API, Tauri commands and the tray
+pub type SharedState = Arc<Mutex<AppState>>;
 
 // Global application state
-pub static APP_STATE: Lazy<Arc<Mutex<AppState>>> = Lazy::new(|| Arc::new(Mutex::new(AppState::default())));
+pub static APP_STATE: Lazy<SharedState> = Lazy::new(|| Arc::new(Mutex::new(AppState::default())));
 
-/// Save current app state to disk
-pub fn save_app_state() -> Result<(), String> {
-    let state = APP_STATE.lock().clone();
-    let app_data_dir = crate::utils::get_app_data_dir()?;
-    
-    // Ensure directory exists
-    fs::create_dir_all(&app_data_dir)
-        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
-    
-    let sessions_file = app_data_dir.join("sessions.json");
-    let state_json = serde_json::to_string_pretty(&state)
-        .map_err(|e| format!("Failed to serialize app state: {e}"))?;
-    
-    fs::write(&sessions_file, state_json)
-        .map_err(|e| format!("Failed to write sessions file: {e}"))?;
-    
-    Ok(())
+/// Save the given state to the database
+pub fn persist_state(state: &Mutex<AppState>) -> Result<(), String> {
+    store::with_connection(|conn| {
+        // Snapshot under the DB lock so concurrent saves cannot commit out of order
+        let state = state.lock().clone();
+        store::save_state(conn, &state)
+    })
 }
 
+/// Save current app state to the database
+pub fn save_app_state() -> Result<(), String> {
+    persist_state(&APP_STATE)
+}
 
-/// Load app state from disk
+/// Load app state from the legacy sessions.json file
 pub fn load_app_state() -> Result<AppState, String> {
     let sessions_file = get_sessions_file_path()?;
     
@@ -55,59 +54,48 @@ pub fn load_app_state() -> Result<AppState, String> {
 }
 
 /// Get aggregate state from current tasks
-pub fn get_aggregate_state() -> &'static str {
-    let state = APP_STATE.lock();
-    let states: Vec<&str> = state.tasks.values()
-        .filter(|t| t.state != "DONE")  // Filter out DONE tasks
-        .map(|t| t.state.as_str())
-        .collect();
-    
-    // Priority order: ERROR > PENDING > WORKING > IDLE
-    if states.contains(&"ERROR") {
-        "ERROR"
-    } else if states.contains(&"PENDING") {
-        "PENDING"
-    } else if states.contains(&"WORKING") {
-        "WORKING"
-    } else {
-        "IDLE"
+pub fn get_aggregate_state() -> TaskState {
+    TaskState::aggregate(APP_STATE.lock().tasks.values().map(|t| t.state))
+}
+
+/// Import the legacy sessions.json into a fresh database, then move the file aside
+fn migrate_legacy_sessions_file(conn: &mut rusqlite::Connection) -> Result<(), String> {
+    let sessions_file = get_sessions_file_path()?;
+    if !sessions_file.exists() || store::has_saved_state(conn)? {
+        return Ok(());
     }
+
+    let legacy_state = load_app_state()?;
+    store::save_state(conn, &legacy_state)?;
+
+    let migrated_path = sessions_file.with_extension("json.migrated");
+    fs::rename(&sessions_file, &migrated_path)
+        .map_err(|e| format!("Failed to move migrated sessions file: {e}"))?;
+    restrict_permissions(&migrated_path)?;
+    info!("Migrated {} tasks from sessions.json into the database", legacy_state.tasks.len());
+    Ok(())
 }
 
-/// Initialize app state by loading from disk or starting fresh
+/// Initialize app state by opening the database (running migrations) and loading from it
 pub fn initialize_app_state() -> Result<(), String> {
-    match load_app_state() {
+    store::open_store(&get_database_path()?)?;
+
+    let loaded = store::with_connection(|conn| {
+        if let Err(e) = migrate_legacy_sessions_file(conn) {
+            warn!("Failed to migrate legacy sessions file: {e}");
+        }
+        // Its contents are encrypted in the database now, so don't leave a readable copy behind
+        if encryption::is_enabled() {
+            encryption::remove_plaintext_sessions_file();
+        }
+        store::load_state(conn)
+    });
+
+    match loaded {
         Ok(loaded_state) => {
-            // Clean up old DONE tasks before setting state
-            let current_time = current_timestamp();
-            let mut cleaned_state = loaded_state;
-            let original_count = cleaned_state.tasks.len();
-            
-            // Remove DONE tasks older than 30 seconds
-            cleaned_state.tasks.retain(|_, task| {
-                if task.state == "DONE" {
-                    let age_seconds = current_time - task.updated_at;
-                    age_seconds <= 30
-                } else {
-                    true
-                }
-            });
-            
-            let cleaned_count = cleaned_state.tasks.len();
-            let removed_count = original_count - cleaned_count;
-            
-            if removed_count > 0 {
-                debug!("Cleaned up {removed_count} old DONE tasks on startup");
-                cleaned_state.updated_at = current_time;
-                
-                // Save the cleaned state back to disk to persist the cleanup
-                *APP_STATE.lock() = cleaned_state.clone();
-                if let Err(e) = save_app_state() {
-                    error!("Failed to save cleaned app state: {e}");
-                }
-            } else {
-                *APP_STATE.lock() = cleaned_state;
-            }
+            // Old finished tasks are cleaned up by the retention sweep once the server starts
+            debug!("Loaded {} tasks", loaded_state.tasks.len());
+            *APP_STATE.lock() = loaded_state;
         }
         Err(e) => {
             warn!("Failed to load app state, starting with empty state: {e}");
@@ -115,4 +103,51 @@ pub fn initialize_app_state() -> Result<(), String> {
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_sessions_with_mixed_case_or_unknown_states_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TALLR_DATA_DIR", dir.path());
+        std::env::set_var("TALLR_STATE_KEY_FILE", "1");
+        let task = |id: &str, state: &str| serde_json::json!({
+            "id": id, "projectId": "legacy", "agent": "claude", "title": id, "state": state,
+            "details": null, "createdAt": 1, "updatedAt": 1, "pinned": false, "detectionMethod": null
+        });
+        let legacy = serde_json::json!({
+            "projects": { "legacy": {
+                "id": "legacy", "name": "legacy", "repoPath": "/src/legacy", "preferredIde": "cursor",
+                "githubUrl": null, "createdAt": 1, "updatedAt": 1
+            }},
+            "tasks": {
+                "upper": task("upper", "PENDING"),
+                "lower": task("lower", "working"),
+                "mixed": task("mixed", "Done"),
+                "unknown": task("unknown", "THINKING"),
+            },
+            "debug_data": {},
+            "updated_at": 1,
+            "last_cli_ping": null
+        });
+        fs::write(dir.path().join("sessions.json"), legacy.to_string()).unwrap();
+
+        store::open_store(&dir.path().join("tallr.db")).unwrap();
+        let state = store::with_connection(|conn| {
+            migrate_legacy_sessions_file(conn)?;
+            store::load_state(conn)
+        }).unwrap();
+
+        let state_of = |id: &str| state.tasks[id].state;
+        assert_eq!(state.tasks.len(), 4);
+        assert_eq!(state_of("upper"), TaskState::Pending);
+        assert_eq!(state_of("lower"), TaskState::Working);
+        assert_eq!(state_of("mixed"), TaskState::Done);
+        assert_eq!(state_of("unknown"), TaskState::Idle);
+        assert!(dir.path().join("sessions.json.migrated").exists());
+        assert!(!dir.path().join("sessions.json.backup").exists());
+    }
+}
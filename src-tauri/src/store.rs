@@ -0,0 +1,637 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::encryption;
+use crate::types::{ApiToken, AppState, ArchivedTask, AuthEvent, DebugData, DeliveryStatus, Project, StateTransition, Task, TaskState, WebhookDelivery};
+
+// Embedded SQLite database (opened once at startup by initialize_app_state).
+// Lock order: take DB before APP_STATE when both are needed.
+pub static DB: Lazy<Arc<Mutex<Option<Connection>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+
+/// Schema migrations, applied in order; `PRAGMA user_version` records how many have run
+const MIGRATIONS: &[&str] = &[
+    // v1: initial schema
+    "CREATE TABLE projects (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        repo_path TEXT NOT NULL,
+        preferred_ide TEXT NOT NULL,
+        github_url TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE tasks (
+        id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL,
+        agent TEXT NOT NULL,
+        title TEXT NOT NULL,
+        state TEXT NOT NULL,
+        details TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        pinned INTEGER NOT NULL DEFAULT 0,
+        detection_method TEXT
+    );
+    CREATE TABLE state_transitions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        task_id TEXT NOT NULL,
+        project_id TEXT NOT NULL,
+        agent TEXT NOT NULL,
+        from_state TEXT,
+        to_state TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        source TEXT,
+        detection_method TEXT,
+        details TEXT
+    );
+    CREATE INDEX idx_state_transitions_task ON state_transitions(task_id, timestamp);
+    CREATE INDEX idx_state_transitions_timestamp ON state_transitions(timestamp);
+    CREATE TABLE debug_data (
+        task_id TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER
+    );",
+    // v2: webhook delivery log
+    "CREATE TABLE webhook_deliveries (
+        id TEXT PRIMARY KEY,
+        webhook TEXT NOT NULL,
+        url TEXT NOT NULL,
+        task_id TEXT NOT NULL,
+        from_state TEXT,
+        to_state TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        response_status INTEGER,
+        error TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_webhook_deliveries_created ON webhook_deliveries(created_at);",
+    // v3: webhooks also deliver escalations
+    "ALTER TABLE webhook_deliveries ADD COLUMN event TEXT NOT NULL DEFAULT 'task.transition';",
+    // v4: finished tasks moved off the dashboard by the retention rules
+    "CREATE TABLE archived_tasks (
+        id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL,
+        agent TEXT NOT NULL,
+        title TEXT NOT NULL,
+        state TEXT NOT NULL,
+        details TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        pinned INTEGER NOT NULL DEFAULT 0,
+        detection_method TEXT,
+        archived_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_archived_tasks_archived ON archived_tasks(archived_at);",
+    // v5: named API tokens, stored as SHA-256 hashes
+    "CREATE TABLE api_tokens (
+        name TEXT PRIMARY KEY,
+        scope TEXT NOT NULL,
+        token_hash TEXT NOT NULL UNIQUE,
+        created_at INTEGER NOT NULL,
+        rotated_at INTEGER
+    );",
+    // v6: rejected API requests
+    "CREATE TABLE auth_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp INTEGER NOT NULL,
+        method TEXT NOT NULL,
+        route TEXT NOT NULL,
+        peer TEXT,
+        status INTEGER NOT NULL,
+        token TEXT
+    );",
+    // v7: present when session content is encrypted; holds a sealed known value to check the key
+    "CREATE TABLE encryption (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        key_check TEXT NOT NULL
+    );",
+    // v8: per-task liveness reported by the wrapper
+    "ALTER TABLE tasks ADD COLUMN last_heartbeat INTEGER;",
+];
+
+/// Open (or create) the database at `path` and bring its schema up to date
+pub fn open_store(path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create database directory: {e}"))?;
+    }
+
+    // Owner-only (0600) before SQLite opens it; the WAL and shared-memory files inherit the mode
+    if !path.exists() {
+        std::fs::File::create(path)
+            .map_err(|e| format!("Failed to create database {path:?}: {e}"))?;
+    }
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        let file = std::path::PathBuf::from(file);
+        if file.exists() {
+            crate::utils::restrict_permissions(&file)?;
+        }
+    }
+
+    let mut conn = Connection::open(path)
+        .map_err(|e| format!("Failed to open database {path:?}: {e}"))?;
+
+    // WAL + FULL sync: each committed transaction survives a crash or power loss
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = FULL;")
+        .map_err(|e| format!("Failed to configure database: {e}"))?;
+
+    run_migrations(&mut conn)?;
+    encryption::prepare(&mut conn)?;
+    *DB.lock() = Some(conn);
+    *SAVED.lock() = None;
+    Ok(())
+}
+
+fn run_migrations(conn: &mut Connection) -> Result<(), String> {
+    let current: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read schema version: {e}"))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let tx = conn.transaction()
+            .map_err(|e| format!("Failed to start migration {version}: {e}"))?;
+        tx.execute_batch(migration)
+            .map_err(|e| format!("Failed to apply migration {version}: {e}"))?;
+        tx.pragma_update(None, "user_version", version)
+            .map_err(|e| format!("Failed to record migration {version}: {e}"))?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit migration {version}: {e}"))?;
+        info!("Applied database migration {version}");
+    }
+    Ok(())
+}
+
+/// Run `f` against the open database connection
+pub fn with_connection<T>(f: impl FnOnce(&mut Connection) -> Result<T, String>) -> Result<T, String> {
+    let mut db = DB.lock();
+    let conn = db.as_mut().ok_or("Database is not open")?;
+    f(conn)
+}
+
+/// Whether the database has ever had app state written to it
+pub fn has_saved_state(conn: &Connection) -> Result<bool, String> {
+    conn.query_row("SELECT EXISTS(SELECT 1 FROM meta WHERE key = 'updated_at')", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to query database: {e}"))
+}
+
+/// Rows as of the last save, so the next one only writes what changed.
+/// None until the first save after the database is opened, which rewrites every table.
+#[derive(Default)]
+struct Saved {
+    projects: HashMap<String, Project>,
+    tasks: HashMap<String, Task>,
+    /// Serialized debug data per task
+    debug_data: HashMap<String, String>,
+}
+
+static SAVED: Lazy<Mutex<Option<Saved>>> = Lazy::new(|| Mutex::new(None));
+
+/// Write the app state in one transaction, inserting, updating or deleting only the rows that
+/// changed since the last save
+pub fn save_state(conn: &mut Connection, state: &AppState) -> Result<(), String> {
+    let mut saved = SAVED.lock();
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {e}"))?;
+    let db_err = |e: rusqlite::Error| format!("Failed to save app state: {e}");
+
+    let previous = match saved.take() {
+        Some(previous) => previous,
+        None => {
+            tx.execute_batch("DELETE FROM projects; DELETE FROM tasks; DELETE FROM debug_data;").map_err(db_err)?;
+            Saved::default()
+        }
+    };
+
+    for project in state.projects.values() {
+        if previous.projects.get(&project.id) == Some(project) {
+            continue;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO projects (id, name, repo_path, preferred_ide, github_url, created_at, updated_at)
+             VALUES (?1, seal(?2), seal(?3), ?4, seal(?5), ?6, ?7)",
+            params![project.id, project.name, project.repo_path, project.preferred_ide,
+                    project.github_url, project.created_at, project.updated_at],
+        ).map_err(db_err)?;
+    }
+    for project_id in previous.projects.keys().filter(|id| !state.projects.contains_key(*id)) {
+        tx.execute("DELETE FROM projects WHERE id = ?1", [project_id]).map_err(db_err)?;
+    }
+
+    for task in state.tasks.values() {
+        if previous.tasks.get(&task.id) == Some(task) {
+            continue;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO tasks
+                 (id, project_id, agent, title, state, details, created_at, updated_at, pinned, detection_method, last_heartbeat)
+             VALUES (?1, ?2, ?3, seal(?4), ?5, seal(?6), ?7, ?8, ?9, ?10, ?11)",
+            params![task.id, task.project_id, task.agent, task.title, task.state.as_str(), task.details,
+                    task.created_at, task.updated_at, task.pinned, task.detection_method, task.last_heartbeat],
+        ).map_err(db_err)?;
+    }
+    for task_id in previous.tasks.keys().filter(|id| !state.tasks.contains_key(*id)) {
+        tx.execute("DELETE FROM tasks WHERE id = ?1", [task_id]).map_err(db_err)?;
+    }
+
+    let mut debug_data = HashMap::with_capacity(state.debug_data.len());
+    for (task_id, data) in &state.debug_data {
+        let data = serde_json::to_string(data)
+            .map_err(|e| format!("Failed to serialize debug data: {e}"))?;
+        if previous.debug_data.get(task_id) != Some(&data) {
+            tx.execute("INSERT OR REPLACE INTO debug_data (task_id, data) VALUES (?1, seal(?2))", params![task_id, data])
+                .map_err(db_err)?;
+        }
+        debug_data.insert(task_id.clone(), data);
+    }
+    for task_id in previous.debug_data.keys().filter(|id| !state.debug_data.contains_key(*id)) {
+        tx.execute("DELETE FROM debug_data WHERE task_id = ?1", [task_id]).map_err(db_err)?;
+    }
+
+    let meta: [(&str, Option<i64>); 3] = [
+        ("updated_at", Some(state.updated_at)),
+        ("last_cli_ping", state.last_cli_ping),
+        ("revision", Some(state.revision as i64)),
+    ];
+    for (key, value) in meta {
+        tx.execute("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)", params![key, value])
+            .map_err(db_err)?;
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit app state: {e}"))?;
+    *saved = Some(Saved { projects: state.projects.clone(), tasks: state.tasks.clone(), debug_data });
+    Ok(())
+}
+
+/// Update a single metadata value without rewriting the rest of the state
+pub fn save_meta_value(conn: &Connection, key: &str, value: Option<i64>) -> Result<(), String> {
+    conn.execute("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)", params![key, value])
+        .map(|_| ())
+        .map_err(|e| format!("Failed to save {key}: {e}"))
+}
+
+/// Record a task's latest heartbeat without rewriting the rest of the state
+pub fn save_task_heartbeat(conn: &Connection, task_id: &str, at: i64) -> Result<(), String> {
+    conn.execute("UPDATE tasks SET last_heartbeat = ?2 WHERE id = ?1", params![task_id, at])
+        .map_err(|e| format!("Failed to save heartbeat of task {task_id}: {e}"))?;
+    if let Some(task) = SAVED.lock().as_mut().and_then(|saved| saved.tasks.get_mut(task_id)) {
+        task.last_heartbeat = Some(at);
+    }
+    Ok(())
+}
+
+/// Write one task's debug data without rewriting the rest of the state
+pub fn save_debug_data(conn: &Connection, debug_data: &DebugData) -> Result<(), String> {
+    let data = serde_json::to_string(debug_data)
+        .map_err(|e| format!("Failed to serialize debug data: {e}"))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO debug_data (task_id, data) VALUES (?1, seal(?2))",
+        params![debug_data.task_id, data],
+    )
+    .map_err(|e| format!("Failed to save debug data: {e}"))?;
+    if let Some(saved) = SAVED.lock().as_mut() {
+        saved.debug_data.insert(debug_data.task_id.clone(), data);
+    }
+    Ok(())
+}
+
+/// Read the whole app state back from the database
+pub fn load_state(conn: &Connection) -> Result<AppState, String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to load app state: {e}");
+    let mut state = AppState::default();
+
+    let mut stmt = conn.prepare(
+        "SELECT id, reveal(name), reveal(repo_path), preferred_ide, reveal(github_url), created_at, updated_at FROM projects"
+    ).map_err(db_err)?;
+    let projects = stmt.query_map([], |row| {
+        Ok(Project {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            repo_path: row.get(2)?,
+            preferred_ide: row.get(3)?,
+            github_url: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }).map_err(db_err)?;
+    for project in projects {
+        let project = project.map_err(db_err)?;
+        state.projects.insert(project.id.clone(), project);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, agent, reveal(title), state, reveal(details), created_at, updated_at, pinned, detection_method,
+                last_heartbeat
+         FROM tasks"
+    ).map_err(db_err)?;
+    let tasks = stmt.query_map([], |row| {
+        let state: String = row.get(4)?;
+        Ok(Task {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            agent: row.get(2)?,
+            title: row.get(3)?,
+            state: TaskState::parse_stored(&state),
+            details: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            pinned: row.get(8)?,
+            detection_method: row.get(9)?,
+            last_heartbeat: row.get(10)?,
+        })
+    }).map_err(db_err)?;
+    for task in tasks {
+        let task = task.map_err(db_err)?;
+        state.tasks.insert(task.id.clone(), task);
+    }
+
+    let mut stmt = conn.prepare("SELECT task_id, reveal(data) FROM debug_data").map_err(db_err)?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(db_err)?;
+    for row in rows {
+        let (task_id, data) = row.map_err(db_err)?;
+        match serde_json::from_str::<DebugData>(&data) {
+            Ok(debug_data) => {
+                state.debug_data.insert(task_id, debug_data);
+            }
+            Err(e) => log::warn!("Skipping unreadable debug data for task {task_id}: {e}"),
+        }
+    }
+
+    let meta = |key: &str| -> Result<Option<i64>, String> {
+        conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+            .map(Option::flatten)
+            .map_err(db_err)
+    };
+    state.updated_at = meta("updated_at")?.unwrap_or(0);
+    state.last_cli_ping = meta("last_cli_ping")?;
+    state.revision = meta("revision")?.unwrap_or(0) as u64;
+
+    Ok(state)
+}
+
+/// Move tasks into the archive table in one transaction
+pub fn archive_tasks(conn: &mut Connection, tasks: &[Task], archived_at: i64) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {e}"))?;
+    let db_err = |e: rusqlite::Error| format!("Failed to archive tasks: {e}");
+    for task in tasks {
+        tx.execute(
+            "INSERT OR REPLACE INTO archived_tasks
+                 (id, project_id, agent, title, state, details, created_at, updated_at, pinned, detection_method, archived_at)
+             VALUES (?1, ?2, ?3, seal(?4), ?5, seal(?6), ?7, ?8, ?9, ?10, ?11)",
+            params![task.id, task.project_id, task.agent, task.title, task.state.as_str(), task.details,
+                    task.created_at, task.updated_at, task.pinned, task.detection_method, archived_at],
+        ).map_err(db_err)?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit archived tasks: {e}"))
+}
+
+fn archived_task_from_row(row: &rusqlite::Row) -> rusqlite::Result<ArchivedTask> {
+    let state: String = row.get(4)?;
+    Ok(ArchivedTask {
+        task: Task {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            agent: row.get(2)?,
+            title: row.get(3)?,
+            state: TaskState::parse_stored(&state),
+            details: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            pinned: row.get(8)?,
+            detection_method: row.get(9)?,
+            last_heartbeat: None,
+        },
+        archived_at: row.get(10)?,
+        project_name: row.get(11)?,
+    })
+}
+
+/// Archived tasks matching `query` (all of them when empty), most recently archived first
+pub fn search_archived(conn: &Connection, query: &str, limit: u32) -> Result<Vec<ArchivedTask>, String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to search archived tasks: {e}");
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let pattern = format!("%{escaped}%");
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.project_id, a.agent, reveal(a.title), a.state, reveal(a.details), a.created_at, a.updated_at,
+                a.pinned, a.detection_method, a.archived_at, reveal(p.name)
+         FROM archived_tasks a LEFT JOIN projects p ON p.id = a.project_id
+         WHERE reveal(a.title) LIKE ?1 ESCAPE '\\' OR reveal(a.details) LIKE ?1 ESCAPE '\\' OR a.agent LIKE ?1 ESCAPE '\\'
+            OR a.id LIKE ?1 ESCAPE '\\' OR reveal(p.name) LIKE ?1 ESCAPE '\\'
+         ORDER BY a.archived_at DESC, a.rowid DESC LIMIT ?2"
+    ).map_err(db_err)?;
+    let rows = stmt.query_map(params![pattern, limit], archived_task_from_row).map_err(db_err)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+}
+
+/// Remove a task from the archive and return it
+pub fn take_archived(conn: &mut Connection, task_id: &str) -> Result<Option<ArchivedTask>, String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to restore archived task: {e}");
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {e}"))?;
+    let archived = tx.query_row(
+        "SELECT a.id, a.project_id, a.agent, reveal(a.title), a.state, reveal(a.details), a.created_at, a.updated_at,
+                a.pinned, a.detection_method, a.archived_at, reveal(p.name)
+         FROM archived_tasks a LEFT JOIN projects p ON p.id = a.project_id WHERE a.id = ?1",
+        [task_id],
+        archived_task_from_row,
+    ).optional().map_err(db_err)?;
+    if archived.is_some() {
+        tx.execute("DELETE FROM archived_tasks WHERE id = ?1", [task_id]).map_err(db_err)?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit restored task: {e}"))?;
+    Ok(archived)
+}
+
+/// Append a state transition to the history table
+pub fn insert_transition(conn: &Connection, transition: &StateTransition) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO state_transitions (task_id, project_id, agent, from_state, to_state, timestamp, source, detection_method, details)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, seal(?9))",
+        params![transition.task_id, transition.project_id, transition.agent,
+                transition.from.map(|s| s.as_str()), transition.to.as_str(), transition.timestamp,
+                transition.source, transition.detection_method, transition.details],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to record state transition: {e}"))
+}
+
+/// All recorded transitions for one task, oldest first
+pub fn load_task_transitions(conn: &Connection, task_id: &str) -> Result<Vec<StateTransition>, String> {
+    query_transitions(
+        conn,
+        "SELECT task_id, project_id, agent, from_state, to_state, timestamp, source, detection_method, reveal(details)
+         FROM state_transitions WHERE task_id = ?1 ORDER BY timestamp, id",
+        params![task_id],
+    )
+}
+
+/// Transitions needed to reconstruct state intervals overlapping `[since, until)`:
+/// every transition before `until` for tasks that changed inside the window or were
+/// still in a non-terminal state when it opened
+pub fn load_transitions_in_window(conn: &Connection, since: i64, until: i64) -> Result<Vec<StateTransition>, String> {
+    query_transitions(
+        conn,
+        "SELECT task_id, project_id, agent, from_state, to_state, timestamp, source, detection_method, reveal(details)
+         FROM state_transitions
+         WHERE timestamp < ?2 AND task_id IN (
+             SELECT task_id FROM state_transitions WHERE timestamp >= ?1 AND timestamp < ?2
+             UNION
+             SELECT t1.task_id FROM state_transitions t1
+             WHERE t1.id = (SELECT MAX(t2.id) FROM state_transitions t2
+                            WHERE t2.task_id = t1.task_id AND t2.timestamp < ?1)
+               AND t1.to_state NOT IN ('DONE', 'CANCELLED')
+         )
+         ORDER BY task_id, timestamp, id",
+        params![since, until],
+    )
+}
+
+fn query_transitions(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<Vec<StateTransition>, String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to load state transitions: {e}");
+    let mut stmt = conn.prepare(sql).map_err(db_err)?;
+    let rows = stmt.query_map(params, |row| {
+        let from: Option<String> = row.get(3)?;
+        let to: String = row.get(4)?;
+        Ok(StateTransition {
+            task_id: row.get(0)?,
+            project_id: row.get(1)?,
+            agent: row.get(2)?,
+            from: from.and_then(|s| s.parse().ok()),
+            to: to.parse().unwrap_or_default(),
+            timestamp: row.get(5)?,
+            source: row.get(6)?,
+            detection_method: row.get(7)?,
+            details: row.get(8)?,
+        })
+    }).map_err(db_err)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+}
+
+/// Insert or update a webhook delivery log entry
+pub fn save_delivery(conn: &Connection, delivery: &WebhookDelivery) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO webhook_deliveries
+             (id, webhook, url, task_id, from_state, to_state, status, attempts, response_status, error, created_at, updated_at, event)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![delivery.id, delivery.webhook, delivery.url, delivery.task_id,
+                delivery.from_state.map(|s| s.as_str()), delivery.to_state.as_str(), delivery.status.as_str(),
+                delivery.attempts, delivery.response_status, delivery.error,
+                delivery.created_at, delivery.updated_at, delivery.event],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to save webhook delivery: {e}"))
+}
+
+/// The most recent webhook deliveries, newest first
+pub fn load_deliveries(conn: &Connection, limit: u32) -> Result<Vec<WebhookDelivery>, String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to load webhook deliveries: {e}");
+    let mut stmt = conn.prepare(
+        "SELECT id, webhook, url, task_id, from_state, to_state, status, attempts, response_status, error, created_at, updated_at, event
+         FROM webhook_deliveries ORDER BY created_at DESC, rowid DESC LIMIT ?1"
+    ).map_err(db_err)?;
+    let rows = stmt.query_map([limit], |row| {
+        let from: Option<String> = row.get(4)?;
+        let to: String = row.get(5)?;
+        let status: String = row.get(6)?;
+        Ok(WebhookDelivery {
+            id: row.get(0)?,
+            webhook: row.get(1)?,
+            url: row.get(2)?,
+            event: row.get(12)?,
+            task_id: row.get(3)?,
+            from_state: from.and_then(|s| s.parse().ok()),
+            to_state: to.parse().unwrap_or_default(),
+            status: match status.as_str() {
+                "delivered" => DeliveryStatus::Delivered,
+                "failed" => DeliveryStatus::Failed,
+                _ => DeliveryStatus::Pending,
+            },
+            attempts: row.get(7)?,
+            response_status: row.get(8)?,
+            error: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
+        })
+    }).map_err(db_err)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+}
+
+/// Insert or update a named API token with the hash of its secret
+pub fn save_api_token(conn: &Connection, token: &ApiToken, token_hash: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO api_tokens (name, scope, token_hash, created_at, rotated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![token.name, token.scope.as_str(), token_hash, token.created_at, token.rotated_at],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to save API token: {e}"))
+}
+
+/// Every named API token with its secret's hash, oldest first
+pub fn load_api_tokens(conn: &Connection) -> Result<Vec<(ApiToken, String)>, String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to load API tokens: {e}");
+    let mut stmt = conn.prepare(
+        "SELECT name, scope, token_hash, created_at, rotated_at FROM api_tokens ORDER BY created_at, name"
+    ).map_err(db_err)?;
+    let rows = stmt.query_map([], |row| {
+        let scope: String = row.get(1)?;
+        Ok((
+            ApiToken {
+                name: row.get(0)?,
+                scope: scope.parse().map_err(|e: String| {
+                    rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, e.into())
+                })?,
+                created_at: row.get(3)?,
+                rotated_at: row.get(4)?,
+            },
+            row.get(2)?,
+        ))
+    }).map_err(db_err)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+}
+
+/// Delete a named API token; returns whether it existed
+pub fn delete_api_token(conn: &Connection, name: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM api_tokens WHERE name = ?1", [name])
+        .map(|deleted| deleted > 0)
+        .map_err(|e| format!("Failed to revoke API token: {e}"))
+}
+
+/// Append a rejected request to the audit log, keeping only the newest `keep` entries
+pub fn insert_auth_event(conn: &Connection, event: &AuthEvent, keep: u32) -> Result<(), String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to record auth event: {e}");
+    conn.execute(
+        "INSERT INTO auth_events (timestamp, method, route, peer, status, token) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![event.timestamp, event.method, event.route, event.peer, event.status, event.token],
+    ).map_err(db_err)?;
+    conn.execute("DELETE FROM auth_events WHERE id <= last_insert_rowid() - ?1", [keep])
+        .map(|_| ())
+        .map_err(db_err)
+}
+
+/// The most recent rejected requests, newest first
+pub fn load_auth_events(conn: &Connection, limit: u32) -> Result<Vec<AuthEvent>, String> {
+    let db_err = |e: rusqlite::Error| format!("Failed to load auth events: {e}");
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, method, route, peer, status, token FROM auth_events ORDER BY id DESC LIMIT ?1"
+    ).map_err(db_err)?;
+    let rows = stmt.query_map([limit], |row| {
+        Ok(AuthEvent {
+            timestamp: row.get(0)?,
+            method: row.get(1)?,
+            route: row.get(2)?,
+            peer: row.get(3)?,
+            status: row.get(4)?,
+            token: row.get(5)?,
+        })
+    }).map_err(db_err)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+}
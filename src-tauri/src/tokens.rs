@@ -0,0 +1,112 @@
+use log::{error, info};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use sha2::{Digest, Sha256};
+use crate::auth::{generate_secure_token, tokens_match};
+use crate::store;
+use crate::types::{ApiToken, IssuedToken, TokenScope};
+
+/// Longest accepted token name
+const MAX_NAME_LEN: usize = 64;
+/// Names the audit log uses for the primary and metrics tokens
+const RESERVED_NAMES: [&str; 2] = [PRIMARY_TOKEN_NAME, METRICS_TOKEN_NAME];
+pub const PRIMARY_TOKEN_NAME: &str = "primary";
+pub const METRICS_TOKEN_NAME: &str = "metrics";
+
+/// A named token and the hash of its secret
+type StoredToken = (ApiToken, String);
+
+// Named tokens, read from the database on first use.
+// Lock order: DB before TOKENS.
+static TOKENS: Lazy<RwLock<Option<Vec<StoredToken>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Hex SHA-256 of a token secret. Secrets are 256 random bits, so a fast hash is enough.
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Re-read the tokens after a change
+fn reload(conn: &rusqlite::Connection) -> Result<(), String> {
+    let tokens = store::load_api_tokens(conn)?;
+    *TOKENS.write() = Some(tokens);
+    Ok(())
+}
+
+/// Token names are short identifiers, e.g. `laptop-wrapper` or `grafana`
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let valid_chars = name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if RESERVED_NAMES.contains(&name) {
+        return Err(format!("Token name '{name}' is reserved"));
+    }
+    if name.is_empty() || name.len() > MAX_NAME_LEN || !valid_chars {
+        return Err(format!(
+            "Invalid token name '{name}': use up to {MAX_NAME_LEN} letters, digits, '-', '_' or '.'"
+        ));
+    }
+    Ok(())
+}
+
+/// Name and scope of the named token with this secret, if there is one
+pub fn find(secret: &str) -> Option<(String, TokenScope)> {
+    if TOKENS.read().is_none() {
+        if let Err(e) = store::with_connection(|conn| reload(conn)) {
+            error!("Failed to load API tokens: {e}");
+            return None;
+        }
+    }
+    let hash = hash_secret(secret);
+    let tokens = TOKENS.read();
+    tokens.as_ref()?.iter()
+        .find(|(_, stored)| tokens_match(&hash, stored))
+        .map(|(token, _)| (token.name.clone(), token.scope))
+}
+
+/// Named tokens, oldest first. Secrets are never listed.
+pub fn list() -> Result<Vec<ApiToken>, String> {
+    store::with_connection(|conn| store::load_api_tokens(conn))
+        .map(|tokens| tokens.into_iter().map(|(token, _)| token).collect())
+}
+
+/// Create a token; returns None when the name is taken
+pub fn create(name: &str, scope: TokenScope, now: i64) -> Result<Option<IssuedToken>, String> {
+    validate_name(name)?;
+    store::with_connection(|conn| {
+        if store::load_api_tokens(conn)?.iter().any(|(token, _)| token.name == name) {
+            return Ok(None);
+        }
+        let token = ApiToken { name: name.to_string(), scope, created_at: now, rotated_at: None };
+        let secret = generate_secure_token();
+        store::save_api_token(conn, &token, &hash_secret(&secret))?;
+        reload(conn)?;
+        info!("Created {scope} API token '{name}'");
+        Ok(Some(IssuedToken { token, secret }))
+    })
+}
+
+/// Replace a token's secret, keeping its name and scope; the old secret stops working at once.
+/// Returns None when there is no such token.
+pub fn rotate(name: &str, now: i64) -> Result<Option<IssuedToken>, String> {
+    store::with_connection(|conn| {
+        let Some((mut token, _)) = store::load_api_tokens(conn)?.into_iter().find(|(token, _)| token.name == name) else {
+            return Ok(None);
+        };
+        token.rotated_at = Some(now);
+        let secret = generate_secure_token();
+        store::save_api_token(conn, &token, &hash_secret(&secret))?;
+        reload(conn)?;
+        info!("Rotated API token '{name}'");
+        Ok(Some(IssuedToken { token, secret }))
+    })
+}
+
+/// Revoke a token; returns whether it existed
+pub fn revoke(name: &str) -> Result<bool, String> {
+    store::with_connection(|conn| {
+        let revoked = store::delete_api_token(conn, name)?;
+        reload(conn)?;
+        if revoked {
+            info!("Revoked API token '{name}'");
+        }
+        Ok(revoked)
+    })
+}
@@ -1,4 +1,7 @@
-use tauri::{WebviewWindow, Emitter};
+use tauri::WebviewWindow;
+
+#[cfg(target_os = "macos")]
+use tauri::Emitter;
 
 #[cfg(target_os = "macos")]
 pub fn setup_unified_toolbar(window: &WebviewWindow) -> Result<(), Box<dyn std::error::Error>> {
@@ -0,0 +1,74 @@
+use serde::Serialize;
+use crate::types::TaskState;
+
+/// Structured description of a rejected state transition
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionError {
+    pub error: &'static str,
+    pub task_id: String,
+    pub from: TaskState,
+    pub to: TaskState,
+    pub message: String,
+}
+
+/// Transition table: states a task may move to from its current state.
+/// Re-reporting the current state is always allowed; DONE and CANCELLED are terminal.
+/// A STALE task may resume in any state once its wrapper is heard from again.
+fn allowed_transitions(from: TaskState) -> &'static [TaskState] {
+    use TaskState::*;
+    match from {
+        Idle | Working | Pending | Error | Stale => &[Idle, Working, Pending, Error, Done, Cancelled, Stale],
+        Done => &[Done],
+        Cancelled => &[Cancelled],
+    }
+}
+
+/// Check whether a task may move from `from` to `to`
+pub fn validate_transition(task_id: &str, from: TaskState, to: TaskState) -> Result<(), TransitionError> {
+    if allowed_transitions(from).contains(&to) {
+        return Ok(());
+    }
+
+    Err(TransitionError {
+        error: "invalid_transition",
+        task_id: task_id.to_string(),
+        from,
+        to,
+        message: format!("Task {task_id} cannot move from {from} to {to}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TaskState::*;
+
+    const ALL: [TaskState; 7] = [Idle, Working, Pending, Error, Done, Cancelled, Stale];
+
+    #[test]
+    fn open_states_may_move_anywhere() {
+        for from in [Idle, Working, Pending, Error, Stale] {
+            for to in ALL {
+                assert!(validate_transition("t", from, to).is_ok(), "{from} -> {to}");
+            }
+        }
+    }
+
+    #[test]
+    fn terminal_states_only_allow_re_reporting() {
+        for from in [Done, Cancelled] {
+            for to in ALL {
+                assert_eq!(validate_transition("t", from, to).is_ok(), to == from, "{from} -> {to}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejections_describe_the_transition() {
+        let error = validate_transition("build-42", Done, Working).unwrap_err();
+        assert_eq!(error.error, "invalid_transition");
+        assert_eq!((error.task_id.as_str(), error.from, error.to), ("build-42", Done, Working));
+        assert_eq!(error.message, "Task build-42 cannot move from DONE to WORKING");
+    }
+}
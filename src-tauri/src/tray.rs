@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::{atomic::{AtomicBool, Ordering}, Arc}, time::Duration};
 use parking_lot::Mutex;
 use once_cell::sync::Lazy;
 use tauri::{AppHandle, Manager, menu::{MenuBuilder, MenuItemBuilder}, tray::TrayIconBuilder};
@@ -6,11 +6,16 @@ use image::GenericImageView;
 use crate::constants::*;
 use crate::state::{APP_STATE, get_aggregate_state};
 use crate::commands::open_ide_and_terminal;
+use crate::types::TaskState;
 
 // Store tray icon globally so we can update it
 static TRAY_ICON: Lazy<Arc<Mutex<Option<tauri::tray::TrayIcon<tauri::Wry>>>>> = 
     Lazy::new(|| Arc::new(Mutex::new(None)));
 
+// Whether the blink loop for escalated tasks is running
+static BLINKING: AtomicBool = AtomicBool::new(false);
+const BLINK_INTERVAL: Duration = Duration::from_millis(700);
+
 pub fn setup_tray_icon(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     let app_handle = app.handle().clone();
     
@@ -42,19 +47,23 @@ fn build_tray_menu(app_handle: &AppHandle) -> Result<tauri::menu::Menu<tauri::Wr
     // Get current app state to build session items
     let state = APP_STATE.lock();
     
-    // Add session items if any exist (filter out DONE tasks)
-    let active_tasks: Vec<_> = state.tasks.iter().filter(|(_, task)| task.state != "DONE").collect();
+    // Add session items if any exist (filter out DONE and CANCELLED tasks, stale ones get their own section)
+    let active_tasks: Vec<_> = state.tasks.iter()
+        .filter(|(_, task)| !task.state.is_terminal() && task.state != TaskState::Stale)
+        .collect();
+    let stale_tasks: Vec<_> = state.tasks.iter().filter(|(_, task)| task.state == TaskState::Stale).collect();
     if !active_tasks.is_empty() {
         for (task_id, task) in active_tasks {
             let project = state.projects.get(&task.project_id);
             let project_name = project.map(|p| &p.name).unwrap_or(&task.project_id);
             
-            let status_icon = match task.state.as_str() {
-                "PENDING" => "🟡",  // Yellow circle for pending
-                "WORKING" => "🔵",  // Blue circle for working
-                "ERROR" => "🔴",    // Red circle for error
-                "IDLE" => "⚫",     // Black circle for idle
-                _ => "⚪"           // White circle for unknown
+            let status_icon = match task.state {
+                TaskState::Pending => "🟡",  // Yellow circle for pending
+                TaskState::Working => "🔵",  // Blue circle for working
+                TaskState::Error => "🔴",    // Red circle for error
+                TaskState::Idle => "⚫",     // Black circle for idle
+                TaskState::Stale => "👻",    // Ghost for sessions whose wrapper went away
+                TaskState::Done | TaskState::Cancelled => "⚪" // White circle for finished
             };
             
             let menu_text = format!("{} {} - {} - {}", status_icon, project_name, task.agent, task.state);
@@ -77,6 +86,26 @@ fn build_tray_menu(app_handle: &AppHandle) -> Result<tauri::menu::Menu<tauri::Wr
         );
         menu_builder = menu_builder.separator();
     }
+
+    // Stale sessions are listed apart so they don't read as running
+    if !stale_tasks.is_empty() {
+        menu_builder = menu_builder.item(
+            &MenuItemBuilder::new(format!("Stale sessions ({})", stale_tasks.len()))
+                .id("stale_sessions")
+                .enabled(false)
+                .build(app_handle)?
+        );
+        for (task_id, task) in stale_tasks {
+            let project = state.projects.get(&task.project_id);
+            let project_name = project.map(|p| &p.name).unwrap_or(&task.project_id);
+            menu_builder = menu_builder.item(
+                &MenuItemBuilder::new(format!("👻 {} - {}", project_name, task.agent))
+                    .id(format!("session_{task_id}"))
+                    .build(app_handle)?
+            );
+        }
+        menu_builder = menu_builder.separator();
+    }
     
     // Add static menu items
     menu_builder = menu_builder
@@ -155,11 +184,11 @@ fn handle_tray_menu_event(app_handle: &AppHandle, menu_id: &str) {
 }
 
 // Function to load tray icon based on state
-fn load_tray_icon(state: &str) -> tauri::image::Image<'static> {
+fn load_tray_icon(state: TaskState) -> tauri::image::Image<'static> {
     let icon_bytes = match state {
-        "PENDING" => TRAY_ICON_PENDING,
-        "ERROR" => TRAY_ICON_ERROR,
-        "WORKING" => TRAY_ICON_WORKING,
+        TaskState::Pending => TRAY_ICON_PENDING,
+        TaskState::Error => TRAY_ICON_ERROR,
+        TaskState::Working => TRAY_ICON_WORKING,
         _ => TRAY_ICON_DEFAULT,
     };
     
@@ -197,5 +226,41 @@ pub fn update_tray_menu(app_handle: &AppHandle) {
         // Load and set the appropriate icon
         let icon = load_tray_icon(aggregate_state);
         let _ = tray.set_icon(Some(icon));
+
+        // Mention stale sessions, which the icon ignores
+        let stale = APP_STATE.lock().tasks.values().filter(|task| task.state == TaskState::Stale).count();
+        let tooltip = match stale {
+            0 => "Tallr".to_string(),
+            count => format!("Tallr - {count} stale session(s)"),
+        };
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+    sync_blinking();
+}
+
+// Start alternating the tray icon while a task is escalated; the loop stops itself once none is
+fn sync_blinking() {
+    if !crate::escalation::blinking() || BLINKING.swap(true, Ordering::SeqCst) {
+        return;
     }
-}
\ No newline at end of file
+
+    tauri::async_runtime::spawn(async {
+        let mut interval = tokio::time::interval(BLINK_INTERVAL);
+        let mut dimmed = false;
+        loop {
+            interval.tick().await;
+            let blinking = crate::escalation::blinking();
+            dimmed = blinking && !dimmed;
+            let icon = load_tray_icon(if dimmed { TaskState::Idle } else { get_aggregate_state() });
+            if let Some(tray) = TRAY_ICON.lock().as_ref() {
+                let _ = tray.set_icon(Some(icon));
+            }
+            if !blinking {
+                BLINKING.store(false, Ordering::SeqCst);
+                // An escalation may have started while this loop was stopping
+                sync_blinking();
+                break;
+            }
+        }
+    });
+}
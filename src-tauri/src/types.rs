@@ -1,5 +1,94 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::{fmt, str::FromStr};
+
+// Task lifecycle state (serialized as the uppercase names the CLI wrappers send)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TaskState {
+    #[default]
+    Idle,
+    Working,
+    Pending,
+    Error,
+    Done,
+    Cancelled,
+    /// The wrapper stopped sending heartbeats, e.g. it was killed or its terminal closed
+    Stale,
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskState::Idle => "IDLE",
+            TaskState::Working => "WORKING",
+            TaskState::Pending => "PENDING",
+            TaskState::Error => "ERROR",
+            TaskState::Done => "DONE",
+            TaskState::Cancelled => "CANCELLED",
+            TaskState::Stale => "STALE",
+        }
+    }
+
+    /// Terminal states end a session and are excluded from the aggregate state
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, TaskState::Done | TaskState::Cancelled)
+    }
+
+    /// Headline state of a set of tasks, ignoring terminal and stale ones.
+    /// Priority order: ERROR > PENDING > WORKING > IDLE
+    pub fn aggregate(states: impl IntoIterator<Item = TaskState>) -> TaskState {
+        states
+            .into_iter()
+            .filter(|state| !state.is_terminal() && *state != TaskState::Stale)
+            .max_by_key(|state| match state {
+                TaskState::Error => 3,
+                TaskState::Pending => 2,
+                TaskState::Working => 1,
+                _ => 0,
+            })
+            .unwrap_or(TaskState::Idle)
+    }
+}
+
+impl TaskState {
+    /// Parse a stored state in any case; states this version does not know (e.g. written by
+    /// an older one, when states were free-form) are read as IDLE rather than failing the load
+    pub fn parse_stored(state: &str) -> TaskState {
+        state.parse().unwrap_or_else(|e| {
+            log::warn!("{e}; treating it as IDLE");
+            TaskState::Idle
+        })
+    }
+}
+
+/// Deserialize a stored task state with `TaskState::parse_stored`
+fn stored_task_state<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TaskState, D::Error> {
+    String::deserialize(deserializer).map(|state| TaskState::parse_stored(&state))
+}
+
+impl fmt::Display for TaskState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "IDLE" => Ok(TaskState::Idle),
+            "WORKING" => Ok(TaskState::Working),
+            "PENDING" => Ok(TaskState::Pending),
+            "ERROR" => Ok(TaskState::Error),
+            "DONE" => Ok(TaskState::Done),
+            "CANCELLED" => Ok(TaskState::Cancelled),
+            "STALE" => Ok(TaskState::Stale),
+            other => Err(format!("Unknown task state: {other}")),
+        }
+    }
+}
 
 // Input types for API requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,7 +106,7 @@ pub struct TaskIn {
     pub id: String,
     pub agent: String,
     pub title: String,
-    pub state: String,
+    pub state: TaskState,
     pub details: Option<String>,
     pub source: Option<String>,
 }
@@ -30,10 +119,113 @@ pub struct AppState {
     pub debug_data: HashMap<String, DebugData>,
     pub updated_at: i64,
     pub last_cli_ping: Option<i64>,
+    #[serde(default)]
+    pub revision: u64,
+}
+
+impl AppState {
+    /// Advance the change revision, returning the revision for the next event
+    pub fn next_revision(&mut self) -> u64 {
+        self.revision += 1;
+        self.revision
+    }
+
+    /// Copy of the state without debug buffers, for clients resyncing after a revision gap
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            revision: self.revision,
+            projects: self.projects.clone(),
+            tasks: self.tasks.clone(),
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// Apply a change event to a client-side copy of the state.
+    /// Returns false when the event does not follow the current revision, so the caller must resync.
+    pub fn apply_event(&mut self, event: &TaskEvent) -> bool {
+        if event.revision() != self.revision + 1 {
+            return false;
+        }
+
+        match event {
+            TaskEvent::TaskCreated { task, .. } => {
+                self.tasks.insert(task.id.clone(), task.clone());
+            }
+            TaskEvent::TaskUpdated { task_id, changes, .. } => {
+                let Some(task) = self.tasks.get_mut(task_id) else { return false };
+                let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(&*task) else { return false };
+                fields.extend(changes.clone());
+                match serde_json::from_value(serde_json::Value::Object(fields)) {
+                    Ok(updated) => *task = updated,
+                    Err(_) => return false,
+                }
+            }
+            TaskEvent::TaskDeleted { task_id, .. } => {
+                self.tasks.remove(task_id);
+            }
+            TaskEvent::ProjectUpdated { project, .. } => {
+                self.projects.insert(project.id.clone(), project.clone());
+            }
+        }
+        self.revision = event.revision();
+        true
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    pub revision: u64,
+    pub projects: HashMap<String, Project>,
+    pub tasks: HashMap<String, Task>,
+    pub updated_at: i64,
+}
+
+// Incremental change events; the tag doubles as the Tauri/SSE event name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case", rename_all_fields = "camelCase")]
+pub enum TaskEvent {
+    TaskCreated {
+        revision: u64,
+        task: Task,
+    },
+    TaskUpdated {
+        revision: u64,
+        task_id: String,
+        changes: serde_json::Map<String, serde_json::Value>,
+    },
+    TaskDeleted {
+        revision: u64,
+        task_id: String,
+    },
+    ProjectUpdated {
+        revision: u64,
+        project: Project,
+    },
+}
+
+impl TaskEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskEvent::TaskCreated { .. } => "task-created",
+            TaskEvent::TaskUpdated { .. } => "task-updated",
+            TaskEvent::TaskDeleted { .. } => "task-deleted",
+            TaskEvent::ProjectUpdated { .. } => "project-updated",
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        match self {
+            TaskEvent::TaskCreated { revision, .. }
+            | TaskEvent::TaskUpdated { revision, .. }
+            | TaskEvent::TaskDeleted { revision, .. }
+            | TaskEvent::ProjectUpdated { revision, .. } => *revision,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Project {
     pub id: String,
     pub name: String,
@@ -44,19 +236,38 @@ pub struct Project {
     pub updated_at: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Task {
     pub id: String,
     pub project_id: String,
     pub agent: String,
     pub title: String,
-    pub state: String,
+    #[serde(deserialize_with = "stored_task_state")]
+    pub state: TaskState,
     pub details: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
     pub pinned: bool,
     pub detection_method: Option<String>,
+    /// When the wrapper last reported it was alive; tasks that never sent a heartbeat are never marked STALE
+    #[serde(default)]
+    pub last_heartbeat: Option<i64>,
+}
+
+// One recorded state change; `from` is None when the task was created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateTransition {
+    pub task_id: String,
+    pub project_id: String,
+    pub agent: String,
+    pub from: Option<TaskState>,
+    pub to: TaskState,
+    pub timestamp: i64,
+    pub source: Option<String>,
+    pub detection_method: Option<String>,
+    pub details: Option<String>,
 }
 
 // Request/Response types
@@ -71,7 +282,7 @@ pub struct UpsertRequest {
 #[serde(rename_all = "camelCase")]
 pub struct StateUpdateRequest {
     pub task_id: String,
-    pub state: String,
+    pub state: TaskState,
     pub details: Option<String>,
     pub detection_method: Option<String>,
     pub source: Option<String>,
@@ -105,6 +316,158 @@ pub struct TaskPinRequest {
     pub pinned: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSnoozeRequest {
+    pub task_id: String,
+    /// 0 lifts the snooze
+    pub minutes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatRequest {
+    pub task_id: String,
+    /// The wrapper's current state, restored if the task had been marked STALE
+    pub state: Option<TaskState>,
+}
+
+// Archive types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskArchiveRequest {
+    pub task_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveQuery {
+    /// Matched against title, details, agent, task id and project name
+    pub q: Option<String>,
+    pub limit: Option<u32>,
+}
+
+// A task taken off the dashboard, with when it was archived
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedTask {
+    #[serde(flatten)]
+    pub task: Task,
+    pub project_name: Option<String>,
+    pub archived_at: i64,
+}
+
+// Analytics types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsQuery {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsReport {
+    pub since: i64,
+    pub until: i64,
+    pub by_project: Vec<StatsBucket>,
+    pub by_agent: Vec<StatsBucket>,
+    pub by_day: Vec<StatsBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsBucket {
+    pub key: String,
+    pub label: String,
+    pub working_seconds: i64,
+    pub pending_seconds: i64,
+    pub sessions: usize,
+    pub error_sessions: usize,
+    pub error_rate: f64,
+    pub median_response_seconds: Option<i64>,
+}
+
+// Digest report types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Daily,
+    Weekly,
+}
+
+impl ReportPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportPeriod::Daily => "daily",
+            ReportPeriod::Weekly => "weekly",
+        }
+    }
+
+    pub fn seconds(&self) -> i64 {
+        match self {
+            ReportPeriod::Daily => 24 * 60 * 60,
+            ReportPeriod::Weekly => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl FromStr for ReportPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "daily" | "day" => Ok(ReportPeriod::Daily),
+            "weekly" | "week" => Ok(ReportPeriod::Weekly),
+            _ => Err(format!("Unknown report period: {s} (expected daily or weekly)")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportQuery {
+    /// `json` (default) or `markdown`
+    pub format: Option<String>,
+    pub until: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestReport {
+    pub period: ReportPeriod,
+    pub since: i64,
+    pub until: i64,
+    pub totals: StatsBucket,
+    pub by_project: Vec<StatsBucket>,
+    pub longest_waits: Vec<DigestWait>,
+    pub open_tasks: Vec<DigestTask>,
+}
+
+// A stretch of time a task spent PENDING
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestWait {
+    pub task_id: String,
+    pub project: String,
+    pub agent: String,
+    pub title: Option<String>,
+    pub started_at: i64,
+    pub seconds: i64,
+    /// Still waiting when the report was made
+    pub ongoing: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestTask {
+    pub task_id: String,
+    pub project: String,
+    pub agent: String,
+    pub title: String,
+    pub state: TaskState,
+    pub since: i64,
+}
+
 // Setup and status types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -125,6 +488,9 @@ pub struct DebugData {
     pub pattern_tests: Option<serde_json::Value>,
     pub confidence: Option<String>,
     pub is_active: Option<bool>,
+    /// Secrets masked in the buffer and history before it was stored
+    #[serde(default)]
+    pub redactions: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -153,6 +519,362 @@ pub struct AppSettings {
     pub preferred_ide: String,
     pub theme: String,
     pub notifications_enabled: bool,
+    /// HTTP listen address as `host:port` (overridden by `TALLR_ADDR` / `TALLR_PORT`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_address: Option<String>,
+    /// Optional Unix domain socket to serve the API on (overridden by `TALLR_SOCKET`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket_path: Option<String>,
+    /// Ordered notification rules; the first match decides, otherwise PENDING and ERROR notify
+    #[serde(default)]
+    pub notification_rules: Vec<NotificationRule>,
+    /// Repeats of the same notification for a task within this window are collapsed (default 60s)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_cooldown_seconds: Option<u64>,
+    /// Daily do-not-disturb window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+    /// URLs that receive a signed POST for each matching state transition
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Reminders for tasks left PENDING or in ERROR
+    #[serde(default)]
+    pub escalation: EscalationSettings,
+    /// Write digest reports to the app data directory on a schedule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_schedule: Option<ReportSchedule>,
+    /// How long finished tasks and debug data are kept
+    #[serde(default)]
+    pub retention: RetentionSettings,
+    /// Masking of secrets in task details and terminal buffers
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    /// When tasks whose wrapper stopped sending heartbeats are marked STALE
+    #[serde(default)]
+    pub heartbeat: HeartbeatSettings,
+}
+
+// Cleanup rules applied periodically while the app runs; pinned tasks are never removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RetentionSettings {
+    /// Hours a finished (DONE or CANCELLED) task stays on the dashboard; unset keeps it 30 seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_done_hours: Option<u64>,
+    /// Keep at most this many finished tasks per project, newest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_last_per_project: Option<usize>,
+    /// Cap on each task's debug buffer, in bytes (the oldest output is dropped)
+    pub max_debug_bytes: usize,
+    /// Move expired tasks to the archive instead of deleting them
+    pub archive: bool,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            keep_done_hours: None,
+            keep_last_per_project: None,
+            max_debug_bytes: 64 * 1024,
+            archive: true,
+        }
+    }
+}
+
+// Secrets are masked before task details and debug buffers are stored or emitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RedactionSettings {
+    /// Mask API keys, bearer tokens, AWS keys and private keys
+    pub enabled: bool,
+    /// Extra regular expressions to mask; the first capture group is masked when there is one
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self { enabled: true, patterns: Vec::new() }
+    }
+}
+
+// Daily digests are written at `at` (local `HH:MM`), weekly ones too on `weekly_on`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSchedule {
+    pub at: String,
+    /// Day of the week for the weekly digest, e.g. `Fri`; none skips it
+    pub weekly_on: Option<String>,
+}
+
+// When and how to remind about tasks that have waited too long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EscalationSettings {
+    pub enabled: bool,
+    /// Seconds a task can stay PENDING before the first reminder
+    pub pending_after_seconds: u64,
+    /// Seconds a task can stay in ERROR before the first reminder
+    pub error_after_seconds: u64,
+    /// Seconds between further reminders; 0 sends only one
+    pub repeat_every_seconds: u64,
+    /// Blink the tray icon while any task is escalated
+    pub blink_tray: bool,
+    /// Also deliver `task.escalated` to the matching webhooks
+    pub webhooks: bool,
+}
+
+impl Default for EscalationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pending_after_seconds: 300,
+            error_after_seconds: 300,
+            repeat_every_seconds: 600,
+            blink_tray: true,
+            webhooks: false,
+        }
+    }
+}
+
+// Detection of sessions whose wrapper died without reporting a final state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HeartbeatSettings {
+    pub enabled: bool,
+    /// Seconds without a heartbeat before a task is marked STALE
+    pub stale_after_seconds: u64,
+}
+
+impl Default for HeartbeatSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            stale_after_seconds: 90,
+        }
+    }
+}
+
+// Do-not-disturb window in local time; `start` after `end` spans midnight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    /// `HH:MM`
+    pub start: String,
+    /// `HH:MM`
+    pub end: String,
+    /// Let escalations through during quiet hours
+    #[serde(default)]
+    pub allow_escalations: bool,
+}
+
+/// What to do when a notification rule matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationAction {
+    Notify,
+    NotifyWithSound,
+    Suppress,
+    /// Notify with sound and ask for the user's attention
+    Escalate,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// A notification rule; every condition that is set must match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRule {
+    pub name: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Project name or repo path, `*` matches any run of characters
+    pub project: Option<String>,
+    pub agent: Option<String>,
+    /// States the rule applies to; empty means any state
+    #[serde(default)]
+    pub states: Vec<TaskState>,
+    pub detection_method: Option<String>,
+    /// Regular expression searched for in the task details
+    pub details_pattern: Option<String>,
+    /// Only match once the task has stayed in its state this long
+    pub min_seconds_in_state: Option<u64>,
+    pub action: NotificationAction,
+}
+
+// An outgoing webhook; every filter that is set must match the transition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub name: Option<String>,
+    pub url: String,
+    /// Key for the `X-Tallr-Signature` HMAC; unsigned when not set
+    pub secret: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// States entered that trigger the webhook; empty means any
+    #[serde(default)]
+    pub states: Vec<TaskState>,
+    /// Project name or repo path, `*` matches any run of characters
+    pub project: Option<String>,
+    pub agent: Option<String>,
+}
+
+/// Outcome of delivering one transition to one webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryQuery {
+    pub limit: Option<u32>,
+}
+
+// One row of the webhook delivery log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook: String,
+    pub url: String,
+    pub event: String,
+    pub task_id: String,
+    pub from_state: Option<TaskState>,
+    pub to_state: TaskState,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub response_status: Option<u16>,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+// API token types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    /// Report task state, as the CLI wrappers do
+    Report,
+    /// Read state, history and analytics (dashboards, metrics scrapers)
+    Read,
+    /// Everything, including removing tasks, debug buffers and token management
+    Admin,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Report => "report",
+            TokenScope::Read => "read",
+            TokenScope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "report" => Ok(TokenScope::Report),
+            "read" => Ok(TokenScope::Read),
+            "admin" => Ok(TokenScope::Admin),
+            _ => Err(format!("Unknown token scope: {s} (expected report, read or admin)")),
+        }
+    }
+}
+
+// A named API token; only a hash of its secret is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub name: String,
+    pub scope: TokenScope,
+    pub created_at: i64,
+    pub rotated_at: Option<i64>,
+}
+
+// A token together with its secret, returned once when it is created or rotated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuedToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenCreateRequest {
+    pub name: String,
+    pub scope: TokenScope,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenNameRequest {
+    pub name: String,
+}
+
+// Auth audit types
+// A request turned away by authentication, scope checks or the rate limiter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthEvent {
+    pub timestamp: i64,
+    pub method: String,
+    pub route: String,
+    /// Client address; None for Unix socket connections
+    pub peer: Option<String>,
+    pub status: u16,
+    /// Named token presented, when the rejection was about its scope
+    pub token: Option<String>,
+}
+
+// Requests made with one token since the app started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub token: String,
+    pub requests: u64,
+    pub rejected: u64,
+    pub last_used_at: i64,
+    pub last_route: String,
+    pub last_peer: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthAudit {
+    /// Newest first
+    pub rejected: Vec<AuthEvent>,
+    pub tokens: Vec<TokenUsage>,
+    /// Peers currently held back by the rate limiter
+    pub blocked_peers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditQuery {
+    pub limit: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -170,6 +892,17 @@ impl Default for AppSettings {
             preferred_ide: "cursor".to_string(),
             theme: "light".to_string(),
             notifications_enabled: true,
+            server_address: None,
+            unix_socket_path: None,
+            notification_rules: Vec::new(),
+            notification_cooldown_seconds: None,
+            quiet_hours: None,
+            webhooks: Vec::new(),
+            escalation: EscalationSettings::default(),
+            report_schedule: None,
+            retention: RetentionSettings::default(),
+            redaction: RedactionSettings::default(),
+            heartbeat: HeartbeatSettings::default(),
         }
     }
 }
\ No newline at end of file
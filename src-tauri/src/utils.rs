@@ -1,4 +1,4 @@
-use std::{fs, time::SystemTime, path::Path};
+use std::{fs, time::SystemTime, path::{Path, PathBuf}};
 use log::info;
 use chrono::Local;
 
@@ -14,22 +14,172 @@ pub fn current_timestamp() -> i64 {
         })
 }
 
-/// Get application data directory for macOS
-pub fn get_app_data_dir() -> Result<std::path::PathBuf, String> {
-    let home = std::env::var("HOME").map_err(|_| "Unable to find HOME directory")?;
-    Ok(std::path::PathBuf::from(home).join("Library/Application Support/Tallr"))
+/// Case-insensitive matcher for a whole string, where `*` matches any run of characters
+pub fn glob_regex(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    let glob = regex::escape(pattern).replace(r"\*", ".*");
+    regex::Regex::new(&format!("(?i)^{glob}$"))
 }
 
-/// Get path to sessions file
-pub fn get_sessions_file_path() -> Result<std::path::PathBuf, String> {
+/// Limit a file to the current user (0600); a no-op on other platforms
+pub fn restrict_permissions(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to set permissions on {}: {e}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Get the user's home directory
+fn home_dir() -> Result<PathBuf, String> {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .map_err(|_| "Unable to find HOME directory".to_string())
+}
+
+/// Resolve an XDG base directory, falling back to `$HOME/<default>` when unset or not absolute
+#[cfg(all(unix, not(target_os = "macos")))]
+fn xdg_dir(var: &str, default: &str) -> Result<PathBuf, String> {
+    match std::env::var_os(var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(home_dir()?.join(default)),
+    }
+}
+
+/// Get application data directory (database, auth token, discovery file).
+/// `TALLR_DATA_DIR` overrides the platform default.
+pub fn get_app_data_dir() -> Result<PathBuf, String> {
+    if let Some(dir) = std::env::var_os("TALLR_DATA_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    #[cfg(target_os = "macos")]
+    return Ok(home_dir()?.join("Library/Application Support/Tallr"));
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return {
+        let dir = xdg_dir("XDG_DATA_HOME", ".local/share")?.join("tallr");
+        static MIGRATE: std::sync::Once = std::sync::Once::new();
+        MIGRATE.call_once(|| migrate_legacy_data_dir(&dir));
+        Ok(dir)
+    };
+
+    #[cfg(windows)]
+    return std::env::var("APPDATA")
+        .map(|dir| PathBuf::from(dir).join("Tallr"))
+        .map_err(|_| "Unable to find APPDATA directory".to_string());
+}
+
+/// Move the data directory earlier builds used on Linux (`~/Library/Application Support/Tallr`)
+/// to `dir` the first time it is needed, so the auth token and sessions carry over
+#[cfg(all(unix, not(target_os = "macos")))]
+fn migrate_legacy_data_dir(dir: &Path) {
+    let Ok(legacy) = home_dir().map(|home| home.join("Library/Application Support/Tallr")) else { return };
+    if dir.exists() || !legacy.is_dir() {
+        return;
+    }
+    if let Some(parent) = dir.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            log::warn!("Failed to create {}: {e}", parent.display());
+            return;
+        }
+    }
+    // A rename fails across filesystems; copy instead and leave the old directory in place
+    let moved = fs::rename(&legacy, dir)
+        .map(|_| "Moved")
+        .or_else(|_| copy_dir(&legacy, dir).map(|_| "Copied"));
+    match moved {
+        Ok(how) => info!("{how} data directory from {} to {}", legacy.display(), dir.display()),
+        Err(e) => log::warn!("Failed to migrate data directory from {}: {e}", legacy.display()),
+    }
+}
+
+/// Copy a directory tree, keeping file permissions
+#[cfg(all(unix, not(target_os = "macos")))]
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Name of the app's config directory (the `identifier` in tauri.conf.json)
+const APP_IDENTIFIER: &str = "dev.tallr.desktop";
+
+/// Tauri's app config directory, which holds `settings.json`; for use where there is no running app
+pub fn get_app_config_dir() -> Result<PathBuf, String> {
+    #[cfg(target_os = "macos")]
+    return Ok(home_dir()?.join("Library/Application Support").join(APP_IDENTIFIER));
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return Ok(xdg_dir("XDG_CONFIG_HOME", ".config")?.join(APP_IDENTIFIER));
+
+    #[cfg(windows)]
+    return std::env::var("APPDATA")
+        .map(|dir| PathBuf::from(dir).join(APP_IDENTIFIER))
+        .map_err(|_| "Unable to find APPDATA directory".to_string());
+}
+
+/// Tauri's app data directory, where older versions kept `settings.json`
+pub fn get_app_identifier_data_dir() -> Result<PathBuf, String> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return Ok(xdg_dir("XDG_DATA_HOME", ".local/share")?.join(APP_IDENTIFIER));
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    get_app_config_dir()
+}
+
+/// Get directory for logs and other state that is not worth backing up
+pub fn get_app_state_dir() -> Result<PathBuf, String> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if std::env::var_os("TALLR_DATA_DIR").is_none() {
+        return Ok(xdg_dir("XDG_STATE_HOME", ".local/state")?.join("tallr"));
+    }
+
+    get_app_data_dir()
+}
+
+/// Get path to the legacy sessions file (imported into the database on first run)
+pub fn get_sessions_file_path() -> Result<PathBuf, String> {
     let app_data_dir = get_app_data_dir()?;
     Ok(app_data_dir.join("sessions.json"))
 }
 
-/// Check if CLI is installed at /usr/local/bin/tallr
+/// Get path to the SQLite database holding app state
+pub fn get_database_path() -> Result<PathBuf, String> {
+    let app_data_dir = get_app_data_dir()?;
+    Ok(app_data_dir.join("tallr.db"))
+}
+
+/// Directory the CLI is linked into: `/usr/local/bin` on macOS, `~/.local/bin` elsewhere (no sudo needed)
+pub fn get_cli_install_dir() -> Result<PathBuf, String> {
+    #[cfg(target_os = "macos")]
+    return Ok(PathBuf::from("/usr/local/bin"));
+
+    #[cfg(not(target_os = "macos"))]
+    return match std::env::var_os("XDG_BIN_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(home_dir()?.join(".local/bin")),
+    };
+}
+
+/// Check if the CLI is installed in the install directory (or a system-wide `/usr/local/bin`)
 pub fn is_cli_installed() -> bool {
-    // Check if symlink exists at /usr/local/bin/tallr
-    Path::new("/usr/local/bin/tallr").exists()
+    get_cli_install_dir()
+        .map(|dir| dir.join("tallr").exists())
+        .unwrap_or(false)
+        || Path::new("/usr/local/bin/tallr").exists()
 }
 
 /// Check if setup has been completed
@@ -50,8 +200,7 @@ pub fn mark_setup_completed() -> Result<(), String> {
 
 /// Initialize logging with file rotation
 pub fn setup_logging() -> Result<(), String> {
-    let app_data_dir = get_app_data_dir()?;
-    let logs_dir = app_data_dir.join("logs");
+    let logs_dir = get_app_state_dir()?.join("logs");
     
     // Ensure logs directory exists
     fs::create_dir_all(&logs_dir)
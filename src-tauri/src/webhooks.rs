@@ -0,0 +1,222 @@
+use std::{sync::Arc, time::Duration};
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use regex::Regex;
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use serde_json::Value;
+use sha2::Sha256;
+use crate::store;
+use crate::types::{AppSettings, DeliveryStatus, Project, StateTransition, Task, TaskState, WebhookConfig, WebhookDelivery};
+use crate::utils::{current_timestamp, glob_regex};
+
+/// Event names sent in the payload and the `X-Tallr-Event` header
+const TRANSITION_EVENT: &str = "task.transition";
+const ESCALATION_EVENT: &str = "task.escalated";
+/// Attempts per delivery before it is logged as failed
+const MAX_ATTEMPTS: u32 = 5;
+/// Wait before the first retry; doubled after every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A webhook with its project pattern compiled
+struct CompiledWebhook {
+    config: WebhookConfig,
+    label: String,
+    project: Option<Regex>,
+}
+
+impl CompiledWebhook {
+    fn compile(config: &WebhookConfig) -> Result<Self, String> {
+        let label = config.name.clone().unwrap_or_else(|| config.url.clone());
+        let url = reqwest::Url::parse(&config.url)
+            .map_err(|e| format!("Invalid URL for webhook '{label}': {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Webhook '{label}' must use an http or https URL"));
+        }
+        let project = config.project.as_deref()
+            .map(glob_regex)
+            .transpose()
+            .map_err(|e| format!("Invalid project pattern in webhook '{label}': {e}"))?;
+
+        Ok(Self { config: config.clone(), label, project })
+    }
+
+    fn matches(&self, state: TaskState, agent: &str, project: Option<&Project>) -> bool {
+        let config = &self.config;
+        if !config.enabled || (!config.states.is_empty() && !config.states.contains(&state)) {
+            return false;
+        }
+        if let Some(pattern) = &self.project {
+            if !project.is_some_and(|p| pattern.is_match(&p.name) || pattern.is_match(&p.repo_path)) {
+                return false;
+            }
+        }
+        config.agent.as_ref().is_none_or(|expected| expected.eq_ignore_ascii_case(agent))
+    }
+}
+
+static WEBHOOKS: Lazy<RwLock<Vec<Arc<CompiledWebhook>>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Tallr/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+});
+
+fn compile_all(settings: &AppSettings) -> Result<Vec<Arc<CompiledWebhook>>, String> {
+    settings.webhooks.iter()
+        .map(|config| CompiledWebhook::compile(config).map(Arc::new))
+        .collect()
+}
+
+/// Check that the webhook settings are usable
+pub fn validate(settings: &AppSettings) -> Result<(), String> {
+    compile_all(settings).map(|_| ())
+}
+
+/// Load the webhook settings. Invalid settings leave the current ones in place.
+pub fn configure(settings: &AppSettings) -> Result<(), String> {
+    let webhooks = compile_all(settings)?;
+    info!("Loaded {} webhook(s)", webhooks.len());
+    *WEBHOOKS.write() = webhooks;
+    Ok(())
+}
+
+/// Queue a delivery to every webhook the transition matches.
+/// Called with the app state lock held; the deliveries themselves run in the background.
+pub fn on_transition(transition: &StateTransition, task: &Task, project: Option<&Project>) {
+    let fields = serde_json::json!({
+        "from": transition.from,
+        "to": transition.to,
+        "timestamp": transition.timestamp,
+        "source": transition.source,
+    });
+    queue(TRANSITION_EVENT, transition.from, task, project, fields);
+}
+
+/// Queue a delivery to every webhook matching a task that has been waiting `waited_seconds`
+/// in its state; `reminder` counts the escalations sent since it entered it
+pub fn on_escalation(task: &Task, project: Option<&Project>, waited_seconds: i64, reminder: u32) {
+    let fields = serde_json::json!({
+        "state": task.state,
+        "since": task.updated_at,
+        "waitedSeconds": waited_seconds,
+        "reminder": reminder,
+        "timestamp": current_timestamp(),
+    });
+    queue(ESCALATION_EVENT, None, task, project, fields);
+}
+
+/// Build the payload (`fields` plus the event, task and project) and start one delivery per matching webhook
+fn queue(event: &'static str, from: Option<TaskState>, task: &Task, project: Option<&Project>, fields: Value) {
+    let webhooks: Vec<_> = WEBHOOKS.read().iter()
+        .filter(|webhook| webhook.matches(task.state, &task.agent, project))
+        .cloned()
+        .collect();
+
+    for webhook in webhooks {
+        let now = current_timestamp();
+        let delivery = WebhookDelivery {
+            id: uuid::Uuid::new_v4().to_string(),
+            webhook: webhook.label.clone(),
+            url: webhook.config.url.clone(),
+            event: event.to_string(),
+            task_id: task.id.clone(),
+            from_state: from,
+            to_state: task.state,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            response_status: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let mut payload = fields.clone();
+        payload["event"] = event.into();
+        payload["deliveryId"] = delivery.id.clone().into();
+        payload["task"] = serde_json::json!(task);
+        payload["project"] = serde_json::json!(project);
+        tokio::spawn(deliver(webhook, delivery, payload.to_string()));
+    }
+}
+
+/// The most recent deliveries, newest first
+pub fn recent_deliveries(limit: u32) -> Result<Vec<WebhookDelivery>, String> {
+    store::with_connection(|conn| store::load_deliveries(conn, limit))
+}
+
+/// `sha256=<hex HMAC-SHA256 of the body>`
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Client errors other than timeouts and rate limiting will not succeed on retry
+fn is_permanent(status: StatusCode) -> bool {
+    status.is_client_error() && !matches!(status, StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS)
+}
+
+fn log_delivery(delivery: &WebhookDelivery) {
+    if let Err(e) = store::with_connection(|conn| store::save_delivery(conn, delivery)) {
+        warn!("Failed to log webhook delivery {}: {e}", delivery.id);
+    }
+}
+
+/// POST the payload, retrying with exponential backoff, and keep the delivery log up to date
+async fn deliver(webhook: Arc<CompiledWebhook>, mut delivery: WebhookDelivery, body: String) {
+    let signature = webhook.config.secret.as_deref().map(|secret| sign(secret, &body));
+    let mut backoff = INITIAL_BACKOFF;
+    log_delivery(&delivery);
+
+    loop {
+        delivery.attempts += 1;
+        let mut request = CLIENT.post(&webhook.config.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header("X-Tallr-Event", &delivery.event)
+            .header("X-Tallr-Delivery", &delivery.id)
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header("X-Tallr-Signature", signature);
+        }
+
+        let mut give_up = delivery.attempts >= MAX_ATTEMPTS;
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.response_status = Some(response.status().as_u16());
+                delivery.error = None;
+            }
+            Ok(response) => {
+                delivery.response_status = Some(response.status().as_u16());
+                delivery.error = Some(format!("HTTP {}", response.status()));
+                give_up |= is_permanent(response.status());
+            }
+            Err(e) => {
+                delivery.response_status = None;
+                delivery.error = Some(e.to_string());
+            }
+        }
+        delivery.updated_at = current_timestamp();
+
+        if delivery.status == DeliveryStatus::Delivered {
+            debug!("Delivered {} to webhook '{}'", delivery.id, webhook.label);
+        } else if give_up {
+            delivery.status = DeliveryStatus::Failed;
+            warn!("Webhook '{}' failed after {} attempt(s): {}",
+                  webhook.label, delivery.attempts, delivery.error.as_deref().unwrap_or("unknown error"));
+        }
+        log_delivery(&delivery);
+        if delivery.status != DeliveryStatus::Pending {
+            return;
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
@@ -7,6 +7,7 @@ import UnifiedToolbar from "./components/UnifiedToolbar";
 import { ProjectFilterPills } from "./components/ProjectFilterPills";
 import { FilterPill } from "./components/ui/FilterPill";
 import { DebugPage } from "./components/DebugPage";
+import { ArchivePage } from "./components/ArchivePage";
 import { ErrorDisplay } from "./components/debug/ErrorDisplay";
 import { CliConnectionStatus } from "./components/CliConnectionStatus";
 import { SetupWizard } from "./components/SetupWizard";
@@ -44,7 +45,7 @@ function App() {
   const { appState, isLoading, error, retryConnection } = useAppState();
   const { settings, toggleAlwaysOnTop, toggleTheme, toggleViewMode, toggleNotifications, toggleAutoSortTasks, toggleGroupByProject } = useSettings();
   const [showSetupWizard, setShowSetupWizard] = useState(false);
-  const [currentPage, setCurrentPage] = useState<'tasks' | 'debug'>('tasks');
+  const [currentPage, setCurrentPage] = useState<'tasks' | 'debug' | 'archive'>('tasks');
   const [debugTaskId, setDebugTaskId] = useState<string | null>(null);
   const [showDoneTasks, setShowDoneTasks] = useState(false);
   const [selectedProjectId, setSelectedProjectId] = useState<string | null>(null);
@@ -156,6 +157,18 @@ function App() {
     }
   }, []);
 
+  // Handle moving a task off the dashboard into the archive
+  const handleArchiveTask = useCallback(async (taskId: string) => {
+    logger.userAction("Archive task", { taskId });
+    try {
+      await ApiService.archiveTask(taskId);
+      logger.info("Task archived successfully", { taskId });
+    } catch (error) {
+      logger.error("Failed to archive task", { taskId, error });
+      throw error; // Re-throw so TaskRow can handle the error display
+    }
+  }, []);
+
   // Handle jump to specific task
   const handleJumpToSpecificTask = useCallback(async (taskId: string) => {
     const task = appState.tasks[taskId];
@@ -530,6 +543,7 @@ function App() {
                                       project={project}
                                       viewMode={settings.viewMode}
                                       onDeleteTask={handleDeleteTask}
+                                      onArchiveTask={handleArchiveTask}
                                       onJumpToContext={handleJumpToSpecificTask}
                                       onShowDebug={handleShowDebugForTask}
                                       onTogglePin={handleTogglePin}
@@ -560,6 +574,7 @@ function App() {
                         project={project}
                         viewMode={settings.viewMode}
                         onDeleteTask={handleDeleteTask}
+                        onArchiveTask={handleArchiveTask}
                         onJumpToContext={handleJumpToSpecificTask}
                         onShowDebug={handleShowDebugForTask}
                         onTogglePin={handleTogglePin}
@@ -585,6 +600,10 @@ function App() {
         />
       )}
 
+      {currentPage === 'archive' && settings.viewMode !== 'tally' && (
+        <ArchivePage onBack={() => setCurrentPage('tasks')} />
+      )}
+
       {/* Footer - Hidden in tally mode */}
       {settings.viewMode !== 'tally' && (
         <footer className="p-4 bg-bg-primary text-xs text-text-primary flex justify-between items-center">
@@ -600,6 +619,14 @@ function App() {
             >
               {showDoneTasks ? `${taskCounts.doneTasks} done` : `${taskCounts.activeTasks} tasks`}
             </button>
+            <button
+              onClick={() => setCurrentPage(prev => prev === 'archive' ? 'tasks' : 'archive')}
+              className="px-2 py-1 rounded bg-bg-tertiary/50 text-text-secondary hover:bg-bg-hover/50 transition-colors cursor-pointer"
+              title={currentPage === 'archive' ? 'Back to sessions' : 'Search archived sessions'}
+              aria-label={currentPage === 'archive' ? 'Back to sessions' : 'Search archived sessions'}
+            >
+              Archive
+            </button>
           </div>
           {/* Right cluster: DEV badge, built by */}
           <div className="flex items-center gap-3">
@@ -0,0 +1,122 @@
+import { useCallback, useEffect, useState } from 'react';
+import { ArrowLeft, Circle, RotateCcw, Search } from 'lucide-react';
+import { Button } from '@/components/ui/button';
+import { ArchivedTask } from '@/types';
+import { ApiService } from '@/services/api';
+import { logger } from '@/utils/logger';
+import TaskStateBadge from './TaskStateBadge';
+import { ErrorDisplay } from './debug/ErrorDisplay';
+
+interface ArchivePageProps {
+  onBack: () => void;
+}
+
+// Wait for typing to pause before searching
+const SEARCH_DEBOUNCE_MS = 250;
+
+export function ArchivePage({ onBack }: ArchivePageProps) {
+  const [query, setQuery] = useState('');
+  const [tasks, setTasks] = useState<ArchivedTask[]>([]);
+  const [isLoading, setIsLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+
+  const search = useCallback(async (text: string) => {
+    try {
+      setError(null);
+      setTasks(await ApiService.searchArchive(text || undefined));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to search archive');
+    } finally {
+      setIsLoading(false);
+    }
+  }, []);
+
+  useEffect(() => {
+    const timeout = setTimeout(() => search(query), SEARCH_DEBOUNCE_MS);
+    return () => clearTimeout(timeout);
+  }, [query, search]);
+
+  const handleRestore = useCallback(async (taskId: string) => {
+    logger.userAction("Restore archived task", { taskId });
+    try {
+      await ApiService.restoreTask(taskId);
+      setTasks(prev => prev.filter(task => task.id !== taskId));
+    } catch (err) {
+      logger.error("Failed to restore task", { taskId, error: err });
+      alert("Failed to restore session. A session with the same id may already be on the dashboard.");
+    }
+  }, []);
+
+  return (
+    <div className="h-screen bg-bg-primary flex flex-col pt-11">
+      <div className="flex items-center gap-4 px-6 py-8">
+        <Button
+          variant="ghost"
+          size="sm"
+          onClick={onBack}
+          className="text-text-secondary hover:text-text-primary"
+        >
+          <ArrowLeft size={18} />
+        </Button>
+        <div className="flex-1 flex items-center gap-2 px-3 py-1.5 rounded-md bg-bg-secondary text-text-secondary">
+          <Search size={14} />
+          <input
+            autoFocus
+            value={query}
+            onChange={(e) => setQuery(e.target.value)}
+            placeholder="Search archived sessions"
+            className="flex-1 bg-transparent text-sm text-text-primary outline-none"
+          />
+        </div>
+      </div>
+
+      <div className="flex-1 overflow-y-auto px-6 pb-4">
+        {isLoading && (
+          <div className="flex items-center justify-center gap-3 py-12 text-text-secondary">
+            <Circle className="animate-spin" size={24} />
+            <span className="text-lg">Loading archive...</span>
+          </div>
+        )}
+
+        {error && <ErrorDisplay error={error} />}
+
+        {!isLoading && !error && tasks.length === 0 && (
+          <div className="py-12 text-center text-sm text-text-secondary">
+            {query ? 'No archived sessions match your search.' : 'No archived sessions yet.'}
+          </div>
+        )}
+
+        {tasks.map((task) => (
+          <div
+            key={task.id}
+            className="flex items-center gap-3 py-3 border-b border-border-primary/40"
+          >
+            <div className="flex-1 min-w-0">
+              <div className="flex items-center gap-2 text-sm text-text-primary">
+                <span className="font-semibold truncate">{task.projectName || 'Unknown'}</span>
+                <span className="text-text-secondary">{task.agent}</span>
+                <TaskStateBadge state={task.state} />
+              </div>
+              <div className="text-xs text-text-secondary truncate" title={task.details || task.title}>
+                {task.title}
+              </div>
+              <div className="text-xs text-text-tertiary">
+                Archived {new Date(task.archivedAt * 1000).toLocaleString()}
+              </div>
+            </div>
+            <Button
+              variant="ghost"
+              size="sm"
+              className="text-text-secondary hover:text-text-primary cursor-pointer"
+              onClick={() => handleRestore(task.id)}
+              title="Restore to dashboard"
+            >
+              <RotateCcw size={14} className="mr-1" />
+              Restore
+            </Button>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -5,7 +5,9 @@ import {
   ExternalLink,
   Bug,
   Pin,
-  PinOff
+  PinOff,
+  BellOff,
+  Archive
 } from "lucide-react";
 import {
   DropdownMenu,
@@ -24,6 +26,8 @@ import {
 } from "@/components/ui/dialog";
 import { Button } from "@/components/ui/button";
 import { TaskRowProps } from '@/types';
+import { ApiService } from '@/services/api';
+import { SNOOZE_MINUTES } from '@/lib/constants';
 import { isTaskCompleted, getTaskStateClasses } from '@/lib/sessionHelpers';
 import { cn } from '@/lib/utils';
 import StatusIndicator from './StatusIndicator';
@@ -36,6 +40,7 @@ export default function TaskRow({
   project,
   viewMode,
   onDeleteTask,
+  onArchiveTask,
   onJumpToContext,
   onShowDebug,
   onTogglePin,
@@ -62,6 +67,12 @@ export default function TaskRow({
         case 'pin':
           await onTogglePin(task.id, !task.pinned);
           break;
+        case 'snooze':
+          await ApiService.snoozeTask(task.id, SNOOZE_MINUTES);
+          break;
+        case 'archive':
+          await onArchiveTask(task.id);
+          break;
         case 'delete':
           setShowDeleteDialog(true);
           break;
@@ -70,7 +81,7 @@ export default function TaskRow({
       console.error(`Failed to ${action === 'delete' ? 'mark task as done' : action + ' task'}:`, error);
       alert(`Failed to ${action === 'delete' ? 'mark session as done' : action + ' session'}. Please try again.`);
     }
-  }, [task.id, task.agent, task.pinned, project?.name, onJumpToContext, onShowDebug, onTogglePin, onDeleteTask]);
+  }, [task.id, task.agent, task.pinned, project?.name, onJumpToContext, onShowDebug, onTogglePin, onDeleteTask, onArchiveTask]);
 
   const handleConfirmDelete = useCallback(async () => {
     try {
@@ -174,6 +185,14 @@ export default function TaskRow({
                   {task.pinned ? <PinOff size={14} className="mr-2" /> : <Pin size={14} className="mr-2" />}
                   {task.pinned ? 'Unpin' : 'Pin to top'}
                 </DropdownMenuItem>
+                <DropdownMenuItem onSelect={() => handleDropdownAction('snooze')} className="cursor-pointer">
+                  <BellOff size={14} className="mr-2" />
+                  Snooze {SNOOZE_MINUTES} min
+                </DropdownMenuItem>
+                <DropdownMenuItem onSelect={() => handleDropdownAction('archive')} className="cursor-pointer">
+                  <Archive size={14} className="mr-2" />
+                  Archive
+                </DropdownMenuItem>
                 <DropdownMenuSeparator />
                 <DropdownMenuItem 
                   onSelect={() => handleDropdownAction('delete')}
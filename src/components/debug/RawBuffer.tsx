@@ -9,10 +9,13 @@ interface RawBufferProps {
 
 export function RawBuffer({ debugData, onCopy, copiedStates }: RawBufferProps) {
   const buffer = debugData?.cleanedBuffer || '';
+  const redactions = debugData?.redactions || 0;
   return (
     <div className="flex flex-col h-full min-h-0">
       <div className="flex items-center justify-between mb-4 flex-shrink-0">
-        <h3 className="text-xl font-semibold text-text-primary">Buffer ({buffer.length} chars)</h3>
+        <h3 className="text-xl font-semibold text-text-primary">
+          Buffer ({buffer.length} chars{redactions > 0 && `, ${redactions} secret${redactions === 1 ? '' : 's'} redacted`})
+        </h3>
         <CopyButton onClick={onCopy} copyKey="buffer" copiedStates={copiedStates} />
       </div>
       <pre
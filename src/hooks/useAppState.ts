@@ -1,7 +1,8 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
-import { AppState } from '@/types';
+import { getCurrentWindow, UserAttentionType } from "@tauri-apps/api/window";
+import { AppState, NotificationEvent, TaskEvent } from '@/types';
 import { ApiService, logApiError } from '@/services/api';
 import { notificationService } from '@/services/notificationService';
 import { getErrorMessage, logError } from '@/utils/errorUtils';
@@ -10,58 +11,101 @@ export function useAppState() {
   const [appState, setAppState] = useState<AppState>({ 
     projects: {}, 
     tasks: {}, 
-    updatedAt: 0 
+    updatedAt: 0,
+    revision: 0
   });
   const [isLoading, setIsLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  // Last applied revision; null until the first snapshot has loaded
+  const revisionRef = useRef<number | null>(null);
 
-  // Listen for backend updates
+  // Replace local state with a full snapshot from the backend
+  const applySnapshot = (snapshot: AppState) => {
+    revisionRef.current = snapshot.revision;
+    setAppState(snapshot);
+  };
+
+  // Listen for incremental backend updates
   useEffect(() => {
-    const unlisten = listen<AppState>("tasks-updated", (event) => {
-      const newState = event.payload;
-      
+    const resync = async () => {
+      try {
+        applySnapshot(await ApiService.getState());
+      } catch (error) {
+        logError('useAppState.resync', error instanceof Error ? error : new Error('Unknown error'));
+      }
+    };
+
+    const applyEvent = (event: TaskEvent) => {
+      const lastRevision = revisionRef.current;
+
+      // Ignore events until the initial snapshot arrives, and anything it already covers
+      if (lastRevision === null || event.revision <= lastRevision) {
+        return;
+      }
+
+      // Missed one or more events: fetch a fresh snapshot instead of applying a partial change
+      if (event.revision !== lastRevision + 1) {
+        revisionRef.current = null;
+        resync();
+        return;
+      }
+
+      revisionRef.current = event.revision;
+
       // Use functional state update to avoid stale closure
       setAppState(currentState => {
-        // Create immutable copy of tasks with proper completedAt timestamps
-        const updatedTasks = { ...newState.tasks };
-        
-        Object.values(updatedTasks).forEach(task => {
-          const oldTask = currentState.tasks[task.id];
-          
-          // If task changed to IDLE from any other state, mark completion time
-          if (oldTask && oldTask.state !== 'IDLE' && task.state === 'IDLE') {
-            // Create new task object with completedAt instead of mutating
-            updatedTasks[task.id] = {
-              ...task,
-              completedAt: Date.now()
-            };
+        const next = { ...currentState, revision: event.revision, updatedAt: Date.now() };
+
+        switch (event.kind) {
+          case 'task-created':
+            return { ...next, tasks: { ...currentState.tasks, [event.task.id]: event.task } };
+          case 'task-updated': {
+            const oldTask = currentState.tasks[event.taskId];
+            if (!oldTask) {
+              return next;
+            }
+            const task = { ...oldTask, ...event.changes };
+
+            // If task changed to IDLE from any other state, mark completion time
+            if (oldTask.state !== 'IDLE' && task.state === 'IDLE') {
+              task.completedAt = Date.now();
+            }
+            return { ...next, tasks: { ...currentState.tasks, [event.taskId]: task } };
           }
-        });
-        
-        // Return new state object with updated tasks
-        return {
-          ...newState,
-          tasks: updatedTasks
-        };
+          case 'task-deleted': {
+            const { [event.taskId]: _deleted, ...remainingTasks } = currentState.tasks;
+            return { ...next, tasks: remainingTasks };
+          }
+          case 'project-updated':
+            return { ...next, projects: { ...currentState.projects, [event.project.id]: event.project } };
+        }
       });
-    });
+    };
+
+    const eventNames: TaskEvent['kind'][] = ['task-created', 'task-updated', 'task-deleted', 'project-updated'];
+    const listeners = eventNames.map(name => listen<TaskEvent>(name, (event) => applyEvent(event.payload)));
 
     return () => {
-      unlisten.then(fn => fn());
+      listeners.forEach(unlisten => unlisten.then(fn => fn()));
     };
-  }, []); // Remove dependency to prevent re-creation
+  }, []);
 
   // Listen for notifications
   useEffect(() => {
-    const unlisten = listen<{title: string, body: string}>("show-notification", async (event) => {
+    const unlisten = listen<NotificationEvent>("show-notification", async (event) => {
       // Check if notifications are enabled in settings
       try {
         const settings = await invoke<any>("load_settings");
         if (settings.notificationsEnabled !== false) { // Default to true if not set
           await notificationService.showNotification({
             title: event.payload.title,
-            body: event.payload.body
+            body: event.payload.body,
+            sound: event.payload.sound ? 'default' : undefined
           });
+          // Escalating rules also ask for the user's attention
+          if (event.payload.urgent) {
+            await getCurrentWindow().requestUserAttention(UserAttentionType.Critical);
+          }
         }
       } catch (error) {
         console.error("Failed to check notification settings:", error);
@@ -73,45 +117,12 @@ export function useAppState() {
     };
   }, []);
 
-  // Listen for individual task events (in addition to the bulk updates above)
-  useEffect(() => {
-    const listeners: Promise<any>[] = [];
-
-    // Listen for single task updates
-    listeners.push(listen('task-updated', (event: { payload: any }) => {
-      setAppState(prev => ({
-        ...prev,
-        tasks: {
-          ...prev.tasks,
-          [event.payload.id]: event.payload
-        },
-        updatedAt: Date.now()
-      }));
-    }));
-
-    // Listen for task deletions
-    listeners.push(listen('task-deleted', (event: { payload: string }) => {
-      setAppState(prev => {
-        const { [event.payload]: deleted, ...remainingTasks } = prev.tasks;
-        return {
-          ...prev,
-          tasks: remainingTasks,
-          updatedAt: Date.now()
-        };
-      });
-    }));
-
-    return () => {
-      listeners.forEach(unlisten => unlisten.then(fn => fn()));
-    };
-  }, []);
-
   // Load initial data
   useEffect(() => {
     const loadTasks = async () => {
       try {
         const data = await ApiService.getState();
-        setAppState(data);
+        applySnapshot(data);
         setError(null); // Clear any previous errors
         setIsLoading(false);
       } catch (error) {
@@ -137,7 +148,7 @@ export function useAppState() {
     
     try {
       const data = await ApiService.getState();
-      setAppState(data);
+      applySnapshot(data);
       setError(null);
       setIsLoading(false);
     } catch (error) {
@@ -52,7 +52,7 @@ export function useDebugData(taskId: string | null) {
         } else if (apiError.message.includes('timeout')) {
           errorMessage = 'Connection timeout. Make sure the Tallr app is running.';
         } else if (apiError.message.includes('ECONNREFUSED')) {
-          errorMessage = 'Cannot connect to Tallr backend. Make sure the app is running on port 4317.';
+          errorMessage = 'Cannot connect to Tallr backend. Make sure the Tallr app is running.';
         }
         
         setError(errorMessage);
@@ -2,6 +2,7 @@ import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { LogicalSize } from "@tauri-apps/api/dpi";
+import { EscalationSettings, HeartbeatSettings, NotificationRule, QuietHours, RedactionSettings, ReportSchedule, RetentionSettings, WebhookConfig } from '@/types';
 
 interface WindowPosition {
   x?: number;
@@ -20,6 +21,17 @@ interface AppSettings {
   notificationsEnabled: boolean;
   autoSortTasks: boolean;
   groupByProject: boolean;
+  serverAddress?: string;
+  unixSocketPath?: string;
+  notificationRules?: NotificationRule[];
+  notificationCooldownSeconds?: number;
+  quietHours?: QuietHours;
+  webhooks?: WebhookConfig[];
+  escalation?: EscalationSettings;
+  reportSchedule?: ReportSchedule;
+  retention?: RetentionSettings;
+  redaction?: RedactionSettings;
+  heartbeat?: HeartbeatSettings;
 }
 
 export function useSettings() {
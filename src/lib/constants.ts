@@ -27,7 +27,9 @@ export const TASK_STATE_PRIORITY: Record<TaskState, number> = {
   WORKING: 1,
   IDLE: 2,
   DONE: 3,
-  ERROR: 4
+  ERROR: 4,
+  CANCELLED: 5,
+  STALE: 6
 } as const;
 
 /**
@@ -35,6 +37,11 @@ export const TASK_STATE_PRIORITY: Record<TaskState, number> = {
  */
 export const ONE_HOUR = 60 * 60 * 1000;
 
+/**
+ * Minutes a task's notifications are silenced by "Snooze"
+ */
+export const SNOOZE_MINUTES = 30;
+
 /**
  * CLI agent options for setup wizard
  */
@@ -55,9 +55,13 @@ export function getTaskStateClasses(state: string): string {
       return 'bg-status-working shadow-[0_0_8px_var(--status-working)] animate-pulse';
     case 'completed':
     case 'done':
+    case 'cancelled':
       return 'bg-status-completed';
     case 'error':
       return 'bg-status-error';
+    case 'stale':
+      // Hollow dot: the wrapper stopped sending heartbeats
+      return 'bg-transparent border border-dashed border-status-idle';
     default:
       return 'bg-status-idle';
   }
@@ -1,4 +1,4 @@
-import { AppState } from '@/types';
+import { AppState, ArchivedTask, StateTransition, Task, TaskState } from '@/types';
 import { invoke } from '@tauri-apps/api/core';
 
 // API Configuration for dev/prod environment detection
@@ -6,7 +6,7 @@ import { invoke } from '@tauri-apps/api/core';
 
 export interface TaskStateUpdateRequest {
   taskId: string;
-  state: string;
+  state: TaskState;
 }
 
 export interface DebugData {
@@ -21,15 +21,16 @@ export interface DebugData {
     detectionMethod?: string;
   }>;
   taskId: string;
+  redactions?: number; // secrets masked before the buffer was stored
 }
 
 
 // Modern API Service using Tauri commands (frontend) and HTTP fallback (health check)
 export const ApiService = {
-  // Get application state via Tauri command
+  // Get a full state snapshot (with revision) via Tauri command
   async getState(): Promise<AppState> {
     try {
-      return await invoke<AppState>('get_tasks');
+      return await invoke<AppState>('get_state_snapshot');
     } catch (error) {
       console.error('[API] Failed to get state via Tauri:', error);
       throw new Error('Failed to get application state');
@@ -74,6 +75,56 @@ export const ApiService = {
     }
   },
 
+  // Silence a task's notifications for a number of minutes (0 lifts the snooze)
+  async snoozeTask(taskId: string, minutes: number): Promise<void> {
+    try {
+      await invoke('frontend_snooze_task', { taskId, minutes });
+    } catch (error) {
+      console.error('[API] Failed to snooze task via Tauri:', error);
+      throw new Error('Failed to snooze task');
+    }
+  },
+
+  // Move a task off the dashboard into the archive via Tauri command
+  async archiveTask(taskId: string): Promise<void> {
+    try {
+      await invoke('frontend_archive_task', { taskId });
+    } catch (error) {
+      console.error('[API] Failed to archive task via Tauri:', error);
+      throw new Error('Failed to archive task');
+    }
+  },
+
+  // Put an archived task back on the dashboard via Tauri command
+  async restoreTask(taskId: string): Promise<Task> {
+    try {
+      return await invoke<Task>('frontend_restore_task', { taskId });
+    } catch (error) {
+      console.error('[API] Failed to restore task via Tauri:', error);
+      throw new Error('Failed to restore task');
+    }
+  },
+
+  // Search archived tasks (most recently archived first when the query is empty)
+  async searchArchive(query?: string, limit?: number): Promise<ArchivedTask[]> {
+    try {
+      return await invoke<ArchivedTask[]>('search_archive_cmd', { query, limit });
+    } catch (error) {
+      console.error('[API] Failed to search archive via Tauri:', error);
+      throw new Error('Failed to search archive');
+    }
+  },
+
+  // Get recorded state transitions for a task via Tauri command
+  async getTaskHistory(taskId: string): Promise<StateTransition[]> {
+    try {
+      return await invoke<StateTransition[]>('get_task_history_cmd', { taskId });
+    } catch (error) {
+      console.error('[API] Failed to get task history via Tauri:', error);
+      throw new Error('Failed to get task history');
+    }
+  },
+
   // Get debug data via Tauri command
   async getDebugData(taskId?: string): Promise<DebugData> {
     try {
@@ -8,6 +8,7 @@ import { invoke } from '@tauri-apps/api/core';
 export interface NotificationPayload {
   title: string;
   body: string;
+  sound?: string;
 }
 
 class NotificationService {
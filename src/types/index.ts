@@ -20,12 +20,109 @@ export interface Task {
   completedAt?: number;
   pinned: boolean;
   detectionMethod?: string;
+  lastHeartbeat?: number;
+}
+
+// A task taken off the dashboard, searchable and restorable
+export interface ArchivedTask extends Task {
+  projectName?: string;
+  archivedAt: number;
+}
+
+export interface StateTransition {
+  taskId: string;
+  projectId: string;
+  agent: string;
+  from: TaskState | null;
+  to: TaskState;
+  timestamp: number;
+  source?: string;
+  detectionMethod?: string;
+  details?: string;
 }
 
 export interface AppState {
   projects: Record<string, Project>;
   tasks: Record<string, Task>;
   updatedAt: number;
+  revision: number;
+}
+
+// Incremental change events emitted by the backend, ordered by revision
+export type TaskEvent =
+  | { kind: 'task-created'; revision: number; task: Task }
+  | { kind: 'task-updated'; revision: number; taskId: string; changes: Partial<Task> }
+  | { kind: 'task-deleted'; revision: number; taskId: string }
+  | { kind: 'project-updated'; revision: number; project: Project };
+
+// Notification decided by the backend's notification rules
+export interface NotificationEvent {
+  title: string;
+  body: string;
+  taskId: string;
+  sound: boolean;
+  urgent: boolean;
+}
+
+export type NotificationAction = 'notify' | 'notify-with-sound' | 'suppress' | 'escalate';
+
+export interface NotificationRule {
+  name?: string;
+  enabled?: boolean;
+  project?: string;
+  agent?: string;
+  states?: TaskState[];
+  detectionMethod?: string;
+  detailsPattern?: string;
+  minSecondsInState?: number;
+  action: NotificationAction;
+}
+
+export interface QuietHours {
+  start: string; // HH:MM, local time
+  end: string;
+  allowEscalations?: boolean;
+}
+
+export interface EscalationSettings {
+  enabled?: boolean;
+  pendingAfterSeconds?: number;
+  errorAfterSeconds?: number;
+  repeatEverySeconds?: number;
+  blinkTray?: boolean;
+  webhooks?: boolean;
+}
+
+export interface RetentionSettings {
+  keepDoneHours?: number;
+  keepLastPerProject?: number;
+  maxDebugBytes?: number;
+  archive?: boolean;
+}
+
+export interface RedactionSettings {
+  enabled?: boolean;
+  patterns?: string[]; // regexes; the first capture group is masked when there is one
+}
+
+export interface HeartbeatSettings {
+  enabled?: boolean;
+  staleAfterSeconds?: number;
+}
+
+export interface ReportSchedule {
+  at: string; // HH:MM, local time
+  weeklyOn?: string; // e.g. 'Fri'
+}
+
+export interface WebhookConfig {
+  name?: string;
+  url: string;
+  secret?: string;
+  enabled?: boolean;
+  states?: TaskState[];
+  project?: string;
+  agent?: string;
 }
 
 export interface TaskRowProps {
@@ -33,6 +130,7 @@ export interface TaskRowProps {
   project: Project | undefined;
   viewMode: ViewMode;
   onDeleteTask: (taskId: string) => Promise<void>;
+  onArchiveTask: (taskId: string) => Promise<void>;
   onJumpToContext: (taskId: string) => Promise<void>;
   onShowDebug: (taskId: string) => void;
   onTogglePin: (taskId: string, pinned: boolean) => Promise<void>;
@@ -40,6 +138,6 @@ export interface TaskRowProps {
   hideProjectName?: boolean;
 }
 
-export type TaskState = 'PENDING' | 'WORKING' | 'IDLE' | 'DONE' | 'ERROR';
+export type TaskState = 'PENDING' | 'WORKING' | 'IDLE' | 'DONE' | 'ERROR' | 'CANCELLED' | 'STALE';
 export type BadgeType = 'agent' | 'ide';
 export type ViewMode = 'full' | 'simple' | 'tally';
\ No newline at end of file
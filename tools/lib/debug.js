@@ -6,7 +6,7 @@
 
 import fs from 'fs';
 import path from 'path';
-import os from 'os';
+import { getLogsDir } from './paths.js';
 
 class DebugLogger {
   constructor() {
@@ -31,7 +31,7 @@ class DebugLogger {
 
   setupLogFile() {
     try {
-      const logsDir = path.join(os.homedir(), 'Library', 'Application Support', 'Tallr', 'logs');
+      const logsDir = getLogsDir();
       
       // Ensure logs directory exists
       fs.mkdirSync(logsDir, { recursive: true });
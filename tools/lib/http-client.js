@@ -54,6 +54,41 @@ export class TallrClient {
     }
   }
 
+  /**
+   * Tell Tallr this task's wrapper is still alive; a task marked STALE resumes the given state
+   */
+  async sendHeartbeat(taskId, state) {
+    await this._makeRequestSingle('POST', '/v1/tasks/heartbeat', { taskId, state });
+  }
+
+  /**
+   * Start periodic heartbeats so the task is marked STALE if this process dies without cleaning up
+   */
+  startHeartbeats(taskId, getState, intervalMs = 15000) {
+    // Don't start multiple intervals
+    if (this._heartbeatInterval) {
+      return;
+    }
+
+    this._heartbeatInterval = setInterval(async () => {
+      try {
+        await this.sendHeartbeat(taskId, getState());
+      } catch (error) {
+        // Heartbeat failed, but we keep sending them
+      }
+    }, intervalMs);
+  }
+
+  /**
+   * Stop periodic heartbeats
+   */
+  stopHeartbeats() {
+    if (this._heartbeatInterval) {
+      clearInterval(this._heartbeatInterval);
+      this._heartbeatInterval = null;
+    }
+  }
+
 
   /**
    * Make HTTP request to Tallr backend with simple retry logic
@@ -83,12 +118,16 @@ export class TallrClient {
    */
   _makeRequestSingle(method, path, data) {
     return new Promise((resolve, reject) => {
-      const url = new URL(path, this._currentGateway);
+      // Gateways of the form `unix:/path/to.sock` talk to the app over a Unix domain socket
+      const target = this._currentGateway.startsWith('unix:')
+        ? { socketPath: this._currentGateway.slice('unix:'.length), path }
+        : (() => {
+            const url = new URL(path, this._currentGateway);
+            return { hostname: url.hostname, port: url.port, path: url.pathname };
+          })();
       
       const options = {
-        hostname: url.hostname,
-        port: url.port,
-        path: url.pathname,
+        ...target,
         method: method,
         timeout: 5000,
         headers: {
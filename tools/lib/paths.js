@@ -0,0 +1,39 @@
+/**
+ * Tallr Paths
+ * 
+ * Locates the directories shared with the Rust backend
+ */
+import path from 'path';
+import os from 'os';
+
+// Resolve an XDG base directory, falling back to ~/<fallback> when unset or relative
+function xdgDir(variable, fallback) {
+  const dir = process.env[variable];
+  return dir && path.isAbsolute(dir) ? dir : path.join(os.homedir(), fallback);
+}
+
+/**
+ * App data directory holding auth.token and server.json (TALLR_DATA_DIR overrides)
+ */
+export function getAppDataDir() {
+  if (process.env.TALLR_DATA_DIR) {
+    return process.env.TALLR_DATA_DIR;
+  }
+  if (process.platform === 'darwin') {
+    return path.join(os.homedir(), 'Library', 'Application Support', 'Tallr');
+  }
+  if (process.platform === 'win32') {
+    return path.join(process.env.APPDATA || os.homedir(), 'Tallr');
+  }
+  return path.join(xdgDir('XDG_DATA_HOME', '.local/share'), 'tallr');
+}
+
+/**
+ * Directory for log files (XDG state directory on Linux)
+ */
+export function getLogsDir() {
+  if (process.platform === 'darwin' || process.platform === 'win32' || process.env.TALLR_DATA_DIR) {
+    return path.join(getAppDataDir(), 'logs');
+  }
+  return path.join(xdgDir('XDG_STATE_HOME', '.local/state'), 'tallr', 'logs');
+}
@@ -6,11 +6,11 @@ import { StateTracker } from './lib/state-tracker.js';
 import { getIdeCommand, promptForIdeCommand } from './lib/settings.js';
 import { MAX_BUFFER_SIZE } from './lib/patterns.js';
 import { debug } from './lib/debug.js';
+import { getAppDataDir } from './lib/paths.js';
 import { showLogo } from './logo.js';
 import { execSync } from 'child_process';
 import fs from 'fs';
 import path from 'path';
-import os from 'os';
 
 const IDE_MAPPINGS = {
   'Visual Studio Code': 'code',
@@ -80,8 +80,7 @@ function getAuthToken() {
   
   // Try to read from auth token file (same location as Rust backend)
   try {
-    const appDataDir = path.join(os.homedir(), 'Library', 'Application Support', 'Tallr');
-    const tokenFile = path.join(appDataDir, 'auth.token');
+    const tokenFile = path.join(getAppDataDir(), 'auth.token');
     
     if (fs.existsSync(tokenFile)) {
       const token = fs.readFileSync(tokenFile, 'utf8').trim();
@@ -106,7 +105,27 @@ function detectTallrGateway() {
     return process.env.TALLR_GATEWAY;
   }
   
-  // Use consistent port 4317 for both dev and prod
+  if (process.env.TALLR_SOCKET) {
+    return `unix:${process.env.TALLR_SOCKET}`;
+  }
+  
+  // Use the endpoint the running app advertised in its discovery file
+  try {
+    const discoveryFile = path.join(getAppDataDir(), 'server.json');
+    if (fs.existsSync(discoveryFile)) {
+      const discovery = JSON.parse(fs.readFileSync(discoveryFile, 'utf8'));
+      if (discovery.url) {
+        return discovery.url;
+      }
+      if (discovery.socket) {
+        return `unix:${discovery.socket}`;
+      }
+    }
+  } catch (error) {
+    debug.cli('Failed to read server discovery file', { error: error.message });
+  }
+  
+  // Default port shared by dev and prod
   return 'http://127.0.0.1:4317';
 }
 
@@ -151,6 +170,7 @@ function restoreTerminal() {
 async function updateTaskAndCleanup(state, details) {
   stateTracker.stopDebugUpdates();
   client.stopHealthPings();
+  client.stopHeartbeats();
   await client.updateTaskState(taskId, state, details);
 }
 
@@ -298,6 +318,7 @@ async function main() {
     if (taskCreated) {
       await stateTracker.syncInitialState();
       debug.state('Initial state synced');
+      client.startHeartbeats(taskId, () => stateTracker.currentState);
     }
 
     await runWithPTY(command, commandArgs);